@@ -0,0 +1,204 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::auth::AuthData;
+use crate::common;
+use crate::requests::*;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Progress {
+    pub level: u32,
+    pub xp: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub enum XpSourceType {
+    TimePlayed,
+    MatchWin,
+    FirstWinOfTheDay,
+    Other(String),
+}
+
+#[derive(Debug, Serialize)]
+pub struct XpSource {
+    pub source: XpSourceType,
+    pub amount: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct XpHistoryEntry {
+    pub match_id: String,
+    pub match_start: String,
+    pub start: Progress,
+    pub end: Progress,
+    pub xp_delta: u32,
+    pub sources: Vec<XpSource>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountXp {
+    pub progress: Progress,
+    pub history: Vec<XpHistoryEntry>,
+    pub last_first_win: String,
+    pub next_first_win: String,
+}
+
+impl From<response::Progress> for Progress {
+    fn from(progress: response::Progress) -> Self {
+        Self {
+            level: progress.level,
+            xp: progress.xp,
+        }
+    }
+}
+
+impl From<response::XpSource> for XpSource {
+    fn from(source: response::XpSource) -> Self {
+        let source_type = match source.id.as_str() {
+            "time-played" => XpSourceType::TimePlayed,
+            "match-win" => XpSourceType::MatchWin,
+            "first-win-of-the-day" => XpSourceType::FirstWinOfTheDay,
+            _ => XpSourceType::Other(source.id),
+        };
+
+        Self {
+            source: source_type,
+            amount: source.amount,
+        }
+    }
+}
+
+impl From<response::XpHistoryEntry> for XpHistoryEntry {
+    fn from(entry: response::XpHistoryEntry) -> Self {
+        Self {
+            match_id: entry.id,
+            match_start: entry.match_start,
+            start: entry.start_progress.into(),
+            end: entry.end_progress.into(),
+            xp_delta: entry.xp_delta,
+            sources: entry.xp_sources.into_iter().map(|s| s.into()).collect(),
+        }
+    }
+}
+
+impl From<response::AccountXp> for AccountXp {
+    fn from(account_xp: response::AccountXp) -> Self {
+        Self {
+            progress: account_xp.progress.into(),
+            history: account_xp.history.into_iter().map(|e| e.into()).collect(),
+            last_first_win: account_xp.last_time_granted_first_win,
+            next_first_win: account_xp.next_time_first_win_available,
+        }
+    }
+}
+
+/// Riot only exposes account XP for the logged in player.
+pub async fn get_account_xp(client: &Client, auth_data: &AuthData) -> Result<AccountXp> {
+    let url = format!(
+        "https://pd.{}.a.pvp.net/account-xp/v1/players/{}",
+        "na", auth_data.user_id
+    );
+
+    let mut req = Request::new(client, url, Method::GET);
+    req.append_headers(auth_data.get_auth_headers());
+    req.append_headers(common::get_client_headers().await?);
+
+    let res = req.send().await?;
+
+    let data: response::AccountXp = res.json().await?;
+
+    Ok(data.into())
+}
+
+mod response {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct AccountXp {
+        #[serde(rename = "Progress")]
+        pub progress: Progress,
+
+        #[serde(rename = "History")]
+        pub history: Vec<XpHistoryEntry>,
+
+        #[serde(rename = "LastTimeGrantedFirstWin")]
+        pub last_time_granted_first_win: String,
+
+        #[serde(rename = "NextTimeFirstWinAvailable")]
+        pub next_time_first_win_available: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Progress {
+        #[serde(rename = "Level")]
+        pub level: u32,
+
+        #[serde(rename = "XP")]
+        pub xp: u32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct XpHistoryEntry {
+        #[serde(rename = "ID")]
+        pub id: String,
+
+        #[serde(rename = "MatchStart")]
+        pub match_start: String,
+
+        #[serde(rename = "StartProgress")]
+        pub start_progress: Progress,
+
+        #[serde(rename = "EndProgress")]
+        pub end_progress: Progress,
+
+        #[serde(rename = "XPDelta")]
+        pub xp_delta: u32,
+
+        #[serde(rename = "XPSources")]
+        pub xp_sources: Vec<XpSource>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct XpSource {
+        #[serde(rename = "ID")]
+        pub id: String,
+
+        #[serde(rename = "Amount")]
+        pub amount: u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT_XP: &str = include_str!("../tests/fixtures/account_xp.json");
+
+    #[test]
+    fn test_deserialize_account_xp() {
+        let data: response::AccountXp = serde_json::from_str(ACCOUNT_XP).unwrap();
+        let account_xp: AccountXp = data.into();
+
+        assert_eq!((account_xp.progress.level, account_xp.progress.xp), (87, 3150));
+        assert_eq!(account_xp.next_first_win, "2023-01-01T22:30:00.000Z");
+        assert_eq!(account_xp.history.len(), 2);
+
+        let entry = &account_xp.history[0];
+        assert_eq!(entry.match_id, "11111111-2222-3333-4444-555555555555");
+        assert_eq!((entry.start.level, entry.end.level, entry.end.xp), (86, 87, 150));
+        assert_eq!(entry.xp_delta, 5550);
+        assert!(matches!(entry.sources[0].source, XpSourceType::TimePlayed));
+        assert!(matches!(entry.sources[1].source, XpSourceType::MatchWin));
+        assert_eq!(entry.sources[1].amount, 2400);
+    }
+
+    #[test]
+    fn test_xp_source_types() {
+        let data: response::AccountXp = serde_json::from_str(ACCOUNT_XP).unwrap();
+        let account_xp: AccountXp = data.into();
+
+        let sources = &account_xp.history[1].sources;
+        assert!(matches!(sources[0].source, XpSourceType::FirstWinOfTheDay));
+        assert!(matches!(&sources[1].source, XpSourceType::Other(id) if id == "daily-mission"));
+    }
+}
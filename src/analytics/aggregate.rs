@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::{kills_by_round, percentage};
+use crate::queue::QueueId;
+use crate::r#match::{Match, Player, Side};
+
+const HOUR_MILLIS: i64 = 60 * 60 * 1000;
+const DAY_MILLIS: i64 = 24 * HOUR_MILLIS;
+
+/// Which matches to include. Times are Unix milliseconds, `to` is exclusive.
+#[derive(Debug, Clone, Default)]
+pub struct StatsFilter {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub season_id: Option<String>,
+    pub queue: Option<QueueId>,
+    /// Shifts match start times before bucketing by day of week and hour
+    pub utc_offset_minutes: i32,
+}
+
+impl StatsFilter {
+    pub fn matches(&self, match_: &Match) -> bool {
+        let info = &match_.match_info;
+
+        self.from.is_none_or(|from| info.game_start >= from)
+            && self.to.is_none_or(|to| info.game_start < to)
+            && self.season_id.as_ref().is_none_or(|season| info.season_id == *season)
+            && self.queue.as_ref().is_none_or(|queue| info.queue_id == *queue)
+    }
+
+    /// Day of the week, 0 being Monday, and hour of the day a match started in local time.
+    fn weekday_and_hour(&self, game_start: u64) -> (u32, u32) {
+        let local = game_start as i64 + self.utc_offset_minutes as i64 * 60 * 1000;
+
+        // 1970-01-01 was a Thursday
+        let weekday = (local.div_euclid(DAY_MILLIS) + 3).rem_euclid(7);
+        let hour = local.rem_euclid(DAY_MILLIS) / HOUR_MILLIS;
+
+        (weekday as u32, hour as u32)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct StatLine {
+    pub matches: u32,
+    pub wins: u32,
+    pub rounds: u32,
+    pub rounds_won: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub score: u32,
+    pub damage: u32,
+    pub headshots: u32,
+    pub shots: u32,
+
+    pub win_rate: f64,
+    pub round_win_rate: f64,
+    pub kd: f64,
+    /// Average combat score
+    pub acs: f64,
+    /// Average damage per round
+    pub adr: f64,
+    pub headshot_percentage: f64,
+}
+
+impl StatLine {
+    fn add(&mut self, other: &StatLine) {
+        self.matches += other.matches;
+        self.wins += other.wins;
+        self.rounds += other.rounds;
+        self.rounds_won += other.rounds_won;
+        self.kills += other.kills;
+        self.deaths += other.deaths;
+        self.assists += other.assists;
+        self.score += other.score;
+        self.damage += other.damage;
+        self.headshots += other.headshots;
+        self.shots += other.shots;
+    }
+
+    fn finish(&mut self) {
+        let per_round = |value: u32| if self.rounds == 0 { 0.0 } else { value as f64 / self.rounds as f64 };
+
+        self.win_rate = percentage(self.wins, self.matches);
+        self.round_win_rate = percentage(self.rounds_won, self.rounds);
+        self.kd = self.kills as f64 / self.deaths.max(1) as f64;
+        self.acs = per_round(self.score);
+        self.adr = per_round(self.damage);
+        self.headshot_percentage = percentage(self.headshots, self.shots);
+    }
+}
+
+/// A player's stats over many matches, overall and broken down by match properties.
+/// `by_side` counts a match once for each side the player had rounds on.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct AggregateStats {
+    pub overall: StatLine,
+    pub by_map: BTreeMap<String, StatLine>,
+    pub by_agent: BTreeMap<String, StatLine>,
+    pub by_queue: BTreeMap<String, StatLine>,
+    pub by_side: BTreeMap<String, StatLine>,
+    pub by_party_size: BTreeMap<u32, StatLine>,
+    /// 0 is Monday
+    pub by_weekday: BTreeMap<u32, StatLine>,
+    pub by_hour: BTreeMap<u32, StatLine>,
+}
+
+impl AggregateStats {
+    fn finish(&mut self) {
+        self.overall.finish();
+
+        for buckets in [&mut self.by_map, &mut self.by_agent, &mut self.by_queue, &mut self.by_side] {
+            buckets.values_mut().for_each(StatLine::finish);
+        }
+
+        for buckets in [&mut self.by_party_size, &mut self.by_weekday, &mut self.by_hour] {
+            buckets.values_mut().for_each(StatLine::finish);
+        }
+    }
+}
+
+/// One player's part of one match, split by side where the mode has rounds.
+fn match_lines(match_: &Match, player: &Player) -> (StatLine, BTreeMap<String, StatLine>) {
+    let player_id = player.id.as_str();
+    let won = match_.teams.iter().any(|team| team.team_id == player.team_id && team.won);
+
+    let mut total = StatLine {
+        matches: 1,
+        wins: won as u32,
+        ..Default::default()
+    };
+
+    let mut sides: BTreeMap<String, StatLine> = BTreeMap::new();
+
+    if match_.round_results.is_empty() {
+        total.kills = player.stats.kills;
+        total.deaths = player.stats.deaths;
+        total.assists = player.stats.assists;
+        total.score = player.stats.score;
+        total.rounds = player.stats.rounds_played;
+
+        return (total, sides);
+    }
+
+    let kills = kills_by_round(match_);
+
+    for round in match_.round_results.iter() {
+        let side = match match_.side_of(&player.team_id, round.round_num) {
+            Side::Attack => "Attack",
+            Side::Defense => "Defense",
+        };
+
+        let mut line = StatLine {
+            rounds: 1,
+            rounds_won: (round.winning_team == player.team_id) as u32,
+            ..Default::default()
+        };
+
+        if let Some(stats) = round.stats_for(player_id) {
+            line.score = stats.score;
+
+            for damage in stats.damage.iter() {
+                line.damage += damage.damage;
+                line.headshots += damage.headshots;
+                line.shots += damage.headshots + damage.bodyshots + damage.legshots;
+            }
+        }
+
+        for kill in kills.get(&round.round_num).into_iter().flatten() {
+            line.kills += (kill.killer == player_id) as u32;
+            line.deaths += (kill.victim == player_id) as u32;
+            line.assists += kill.assistants.iter().any(|a| a == player_id) as u32;
+        }
+
+        total.add(&line);
+        sides.entry(side.to_string()).or_default().add(&line);
+    }
+
+    for line in sides.values_mut() {
+        line.matches = 1;
+        line.wins = won as u32;
+    }
+
+    // The kill feed can miss kills the scoreboard counted, so only the side split comes from it
+    total.kills = player.stats.kills;
+    total.deaths = player.stats.deaths;
+    total.assists = player.stats.assists;
+
+    (total, sides)
+}
+
+/// Aggregates a player's stats over the matches passing `filter`. Matches they didn't play are skipped.
+pub fn aggregate<'a>(matches: impl IntoIterator<Item = &'a Match>, player_id: &str, filter: &StatsFilter) -> AggregateStats {
+    let mut stats = AggregateStats::default();
+
+    for match_ in matches.into_iter().filter(|m| filter.matches(m)) {
+        let Some(player) = match_.players.iter().find(|p| p.id == player_id) else {
+            continue;
+        };
+
+        let (line, sides) = match_lines(match_, player);
+
+        let party_size = if player.party_id.is_empty() {
+            1
+        } else {
+            match_.players.iter().filter(|p| p.party_id == player.party_id).count() as u32
+        };
+        let (weekday, hour) = filter.weekday_and_hour(match_.match_info.game_start);
+
+        stats.overall.add(&line);
+        stats.by_map.entry(match_.match_info.map_id.clone()).or_default().add(&line);
+        stats.by_agent.entry(player.agent_id.clone()).or_default().add(&line);
+        stats.by_queue.entry(match_.match_info.queue_id.as_str().to_string()).or_default().add(&line);
+        stats.by_party_size.entry(party_size).or_default().add(&line);
+        stats.by_weekday.entry(weekday).or_default().add(&line);
+        stats.by_hour.entry(hour).or_default().add(&line);
+
+        for (side, line) in sides {
+            stats.by_side.entry(side).or_default().add(&line);
+        }
+    }
+
+    stats.finish();
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH: &str = include_str!("../../tests/fixtures/match.json");
+    const DEATHMATCH: &str = include_str!("../../tests/fixtures/deathmatch.json");
+
+    #[test]
+    fn test_aggregate() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let stats = aggregate([&match_], "00000000-0000-0000-0000-000000000004", &StatsFilter::default());
+
+        let overall = &stats.overall;
+        assert_eq!((overall.matches, overall.wins, overall.rounds, overall.rounds_won), (1, 1, 3, 2));
+        assert_eq!((overall.kills, overall.deaths, overall.assists), (4, 1, 1));
+        assert_eq!(overall.kd, 4.0);
+
+        assert_eq!(stats.by_side["Defense"].rounds, 3);
+        assert!(!stats.by_side.contains_key("Attack"));
+        assert_eq!(stats.by_party_size[&1].matches, 1);
+
+        // 2023-01-01 00:00 UTC was a Sunday
+        assert_eq!(stats.by_weekday[&6].matches, 1);
+        assert_eq!(stats.by_hour[&0].matches, 1);
+    }
+
+    #[test]
+    fn test_aggregate_uses_scoreboard() {
+        let mut json: serde_json::Value = serde_json::from_str(MATCH).unwrap();
+        for player in json["players"].as_array_mut().unwrap() {
+            player["partyId"] = "".into();
+        }
+        json["players"][3]["stats"]["kills"] = 5.into();
+
+        let match_: Match = serde_json::from_value(json).unwrap();
+        let stats = aggregate([&match_], "00000000-0000-0000-0000-000000000004", &StatsFilter::default());
+
+        assert_eq!((stats.overall.kills, stats.overall.deaths), (5, 1));
+        assert_eq!(stats.by_side["Defense"].kills, 4);
+        assert_eq!(stats.by_party_size[&1].matches, 1);
+    }
+
+    #[test]
+    fn test_aggregate_filters() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let deathmatch: Match = serde_json::from_str(DEATHMATCH).unwrap();
+        let player = "00000000-0000-0000-0000-000000000001";
+
+        let filter = StatsFilter {
+            season_id: Some("67e373c7-48f7-b422-641b-079ace30b427".to_string()),
+            to: Some(1672531200001),
+            utc_offset_minutes: -60,
+            ..Default::default()
+        };
+
+        let stats = aggregate([&match_, &deathmatch], player, &filter);
+        assert_eq!(stats.overall.matches, 1);
+        assert_eq!(stats.by_party_size[&2].matches, 1);
+        assert_eq!(stats.by_weekday[&5].matches, 1);
+        assert_eq!(stats.by_hour[&23].matches, 1);
+
+        let filter = StatsFilter {
+            queue: Some(QueueId::Deathmatch),
+            ..Default::default()
+        };
+
+        let stats = aggregate([&match_, &deathmatch], "00000000-0000-0000-0000-00000000000b", &filter);
+        assert_eq!((stats.overall.kills, stats.overall.wins), (40, 1));
+        assert!(stats.by_side.is_empty());
+    }
+}
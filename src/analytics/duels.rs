@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use super::{find_uncredited_trade, is_enemy_kill, kills_by_round, teams_by_player, AliveTracker};
+use crate::r#match::{Match, PlayerId};
+
+/// `kills[i][j]` is how many times `players[i]` killed `players[j]`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuelMatrix {
+    pub players: Vec<PlayerId>,
+    pub kills: Vec<Vec<u32>>,
+}
+
+impl DuelMatrix {
+    pub fn kills_between(&self, killer: &str, victim: &str) -> u32 {
+        let index = |id: &str| self.players.iter().position(|p| p == id);
+
+        match (index(killer), index(victim)) {
+            (Some(k), Some(v)) => self.kills[k][v],
+            _ => 0,
+        }
+    }
+}
+
+/// A death avenged by a teammate of the victim.
+#[derive(Debug, Clone, Serialize)]
+pub struct Trade {
+    pub round: u32,
+    pub traded_player: PlayerId,
+    pub killer: PlayerId,
+    pub trader: PlayerId,
+    /// Milliseconds between the death and the trade
+    pub delay: u32,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct TradeStats {
+    pub player_id: PlayerId,
+    pub ign: String,
+    pub tagline: String,
+    pub deaths: u32,
+    /// Deaths to enemies that were avenged. Suicides and team kills are neither traded nor untraded.
+    pub traded_deaths: u32,
+    pub untraded_deaths: u32,
+    pub trade_kills: u32,
+    /// Teammate deaths to enemies while this player was alive to trade them
+    pub trade_opportunities: u32,
+    /// Share of trade opportunities that were traded by this player
+    pub trade_efficiency: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuelReport {
+    pub matrix: DuelMatrix,
+    pub trades: Vec<Trade>,
+    pub players: Vec<TradeStats>,
+}
+
+/// Who killed whom, and which deaths were traded within `trade_window` milliseconds. Each kill
+/// trades at most one death.
+pub fn duels(match_: &Match, trade_window: u32) -> DuelReport {
+    let teams = teams_by_player(match_);
+    let players: Vec<PlayerId> = match_.players.iter().map(|p| p.id.clone()).collect();
+    let index: HashMap<&str, usize> = players.iter().enumerate().map(|(i, p)| (p.as_str(), i)).collect();
+
+    let mut matrix = vec![vec![0; players.len()]; players.len()];
+    let mut stats: Vec<TradeStats> = match_
+        .players
+        .iter()
+        .map(|p| TradeStats {
+            player_id: p.id.clone(),
+            ign: p.ign.clone(),
+            tagline: p.tagline.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let mut trades = Vec::new();
+
+    for (round, kills) in kills_by_round(match_) {
+        let mut alive = AliveTracker::new(&teams);
+        let mut credited = HashSet::new();
+
+        for (i, kill) in kills.iter().enumerate() {
+            alive.kill(&kill.victim);
+
+            let (Some(killer), Some(victim)) = (index.get(kill.killer.as_str()), index.get(kill.victim.as_str())) else {
+                continue;
+            };
+
+            matrix[*killer][*victim] += 1;
+            stats[*victim].deaths += 1;
+
+            if !is_enemy_kill(kill, &teams) {
+                continue;
+            }
+
+            if let Some(team) = teams.get(kill.victim.as_str()) {
+                for teammate in alive.alive_players(team) {
+                    if let Some(i) = index.get(teammate) {
+                        stats[*i].trade_opportunities += 1;
+                    }
+                }
+            }
+
+            let Some((trade, delay)) = find_uncredited_trade(i, &kills, &teams, trade_window, &credited) else {
+                stats[*victim].untraded_deaths += 1;
+                continue;
+            };
+
+            credited.insert(trade);
+            let trade = kills[trade];
+            stats[*victim].traded_deaths += 1;
+
+            if let Some(trader) = index.get(trade.killer.as_str()) {
+                stats[*trader].trade_kills += 1;
+            }
+
+            trades.push(Trade {
+                round,
+                traded_player: kill.victim.clone(),
+                killer: kill.killer.clone(),
+                trader: trade.killer.clone(),
+                delay,
+            });
+        }
+    }
+
+    for player in stats.iter_mut() {
+        if player.trade_opportunities > 0 {
+            player.trade_efficiency = player.trade_kills as f64 / player.trade_opportunities as f64;
+        }
+    }
+
+    DuelReport {
+        matrix: DuelMatrix { players, kills: matrix },
+        trades,
+        players: stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::DEFAULT_TRADE_WINDOW;
+
+    const MATCH: &str = include_str!("../../tests/fixtures/match.json");
+
+    fn id(n: u32) -> String {
+        format!("00000000-0000-0000-0000-00000000000{}", n)
+    }
+
+    #[test]
+    fn test_duel_matrix() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let report = duels(&match_, DEFAULT_TRADE_WINDOW);
+
+        assert_eq!(report.matrix.kills_between(&id(4), &id(1)), 2);
+        assert_eq!(report.matrix.kills_between(&id(1), &id(4)), 1);
+        assert_eq!(report.matrix.kills_between(&id(1), &id(2)), 0);
+        assert_eq!(report.matrix.kills.iter().flatten().sum::<u32>(), 8);
+    }
+
+    #[test]
+    fn test_trades() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+
+        let report = duels(&match_, DEFAULT_TRADE_WINDOW);
+        assert!(report.trades.is_empty());
+        assert!(report.players.iter().all(|p| p.traded_deaths == 0));
+
+        let report = duels(&match_, 20000);
+        assert_eq!(report.trades.len(), 1);
+
+        let trade = &report.trades[0];
+        assert_eq!((trade.round, trade.delay), (0, 20000));
+        assert_eq!((&trade.traded_player, &trade.trader), (&id(2), &id(1)));
+
+        let alpha = &report.players[0];
+        assert_eq!(alpha.trade_kills, 1);
+        assert_eq!(alpha.trade_opportunities, 1);
+        assert_eq!(alpha.trade_efficiency, 1.0);
+
+        let bravo = &report.players[1];
+        assert_eq!((bravo.deaths, bravo.traded_deaths, bravo.untraded_deaths), (3, 1, 2));
+    }
+
+    #[test]
+    fn test_trades_skip_team_kills_and_credit_once() {
+        let mut json: serde_json::Value = serde_json::from_str(MATCH).unwrap();
+        let mut fifth = json["players"][0].clone();
+        fifth["subject"] = id(5).into();
+        json["players"].as_array_mut().unwrap().push(fifth);
+
+        let kill = |round: u32, round_time: u32, killer: u32, victim: u32| {
+            serde_json::json!({ "round": round, "roundTime": round_time, "killer": id(killer), "victim": id(victim) })
+        };
+        json["kills"] = serde_json::json!([
+            kill(3, 1000, 3, 1),
+            kill(3, 2000, 3, 2),
+            kill(3, 3000, 5, 3),
+            kill(4, 1000, 1, 2),
+            kill(4, 2000, 3, 1),
+        ]);
+
+        let match_: Match = serde_json::from_value(json).unwrap();
+        let report = duels(&match_, DEFAULT_TRADE_WINDOW);
+
+        assert_eq!(report.trades.len(), 1);
+        assert_eq!((&report.trades[0].traded_player, &report.trades[0].trader), (&id(1), &id(5)));
+
+        let stats = |n: u32| report.players.iter().find(|p| p.player_id == id(n)).unwrap();
+        assert_eq!((stats(1).deaths, stats(1).traded_deaths, stats(1).untraded_deaths), (2, 1, 1));
+        assert_eq!((stats(2).deaths, stats(2).traded_deaths, stats(2).untraded_deaths), (2, 0, 1));
+        assert_eq!((stats(5).trade_kills, stats(5).trade_opportunities), (1, 3));
+    }
+}
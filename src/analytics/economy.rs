@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::{percentage, teams_by_player};
+use crate::r#match::{GameMode, Match, TeamColor, HALF_LENGTH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum BuyType {
+    Pistol,
+    Eco,
+    Force,
+    HalfBuy,
+    FullBuy,
+    /// Overtime hands every player the same credits, so there is no buy to classify
+    Overtime,
+}
+
+impl BuyType {
+    /// How much was invested, for comparing buys against each other. Pistol rounds are even.
+    fn strength(&self) -> u8 {
+        match self {
+            BuyType::Eco => 0,
+            BuyType::Pistol | BuyType::Force | BuyType::Overtime => 1,
+            BuyType::HalfBuy => 2,
+            BuyType::FullBuy => 3,
+        }
+    }
+}
+
+/// Thresholds are averages per player on the team.
+#[derive(Debug, Clone)]
+pub struct EconomyThresholds {
+    /// Rounds that start with everyone on pistols. Loss streaks also reset here.
+    pub pistol_rounds: Vec<u32>,
+    /// Below this loadout value the team saved.
+    pub eco_max: u32,
+    /// From this loadout value the team had rifles and full shields.
+    pub full_buy_min: u32,
+    /// A buy in between with less than this left over emptied the bank, so it was forced.
+    pub force_remaining_max: u32,
+    /// First overtime round. From here on the economy is fixed, so loss streaks don't carry over.
+    pub overtime_from: Option<u32>,
+}
+
+impl Default for EconomyThresholds {
+    fn default() -> Self {
+        Self {
+            pistol_rounds: vec![0, 12],
+            eco_max: 2000,
+            full_buy_min: 3900,
+            force_remaining_max: 1000,
+            overtime_from: Some(HALF_LENGTH * 2),
+        }
+    }
+}
+
+impl EconomyThresholds {
+    /// Defaults with the pistol and overtime rounds moved to where the mode swaps sides.
+    pub fn for_mode(mode: GameMode) -> Self {
+        let half_length = mode.half_length().unwrap_or(HALF_LENGTH);
+
+        Self {
+            pistol_rounds: vec![0, half_length],
+            overtime_from: Some(half_length * 2),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_overtime(&self, round: u32) -> bool {
+        self.overtime_from.is_some_and(|from| round >= from)
+    }
+
+    pub fn classify(&self, round: u32, loadout_value: u32, remaining: u32) -> BuyType {
+        if self.is_overtime(round) {
+            BuyType::Overtime
+        } else if self.pistol_rounds.contains(&round) {
+            BuyType::Pistol
+        } else if loadout_value < self.eco_max {
+            BuyType::Eco
+        } else if loadout_value >= self.full_buy_min {
+            BuyType::FullBuy
+        } else if remaining < self.force_remaining_max {
+            BuyType::Force
+        } else {
+            BuyType::HalfBuy
+        }
+    }
+}
+
+/// Credits granted to the losing team, by the number of rounds lost in a row.
+pub fn loss_bonus(loss_streak: u32) -> u32 {
+    match loss_streak {
+        0 => 0,
+        1 => 1900,
+        2 => 2400,
+        _ => 2900,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamRoundEconomy {
+    pub team_id: TeamColor,
+    pub buy_type: BuyType,
+    pub loadout_value: u32,
+    pub spent: u32,
+    pub remaining: u32,
+    /// Rounds lost in a row going into this round
+    pub loss_streak: u32,
+    pub loss_bonus: u32,
+    pub won: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundEconomy {
+    pub round: u32,
+    pub teams: Vec<TeamRoundEconomy>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuyTypeRecord {
+    pub team_id: TeamColor,
+    pub buy_type: BuyType,
+    pub rounds: u32,
+    pub wins: u32,
+    pub win_rate: f64,
+}
+
+/// A round won by the team that invested less. Overtime rounds never count.
+#[derive(Debug, Clone, Serialize)]
+pub struct EconomySwing {
+    pub round: u32,
+    pub winning_team: TeamColor,
+    pub winner_buy: BuyType,
+    pub loser_buy: BuyType,
+    pub loadout_difference: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EconomyReport {
+    pub rounds: Vec<RoundEconomy>,
+    pub buy_types: Vec<BuyTypeRecord>,
+    pub max_loss_streaks: HashMap<String, u32>,
+    pub swings: Vec<EconomySwing>,
+}
+
+pub fn economy(match_: &Match, thresholds: &EconomyThresholds) -> EconomyReport {
+    let teams = teams_by_player(match_);
+
+    let mut team_ids: Vec<&str> = teams.values().copied().collect();
+    team_ids.sort();
+    team_ids.dedup();
+
+    let mut loss_streaks: HashMap<&str, u32> = HashMap::new();
+    let mut max_loss_streaks: HashMap<String, u32> = HashMap::new();
+    let mut records: HashMap<(&str, BuyType), (u32, u32)> = HashMap::new();
+    let mut rounds = Vec::new();
+    let mut swings = Vec::new();
+
+    for round in match_.round_results.iter() {
+        let overtime = thresholds.is_overtime(round.round_num);
+
+        if overtime || thresholds.pistol_rounds.contains(&round.round_num) {
+            loss_streaks.clear();
+        }
+
+        let mut round_teams = Vec::new();
+
+        for team in team_ids.iter() {
+            let economies: Vec<_> = round
+                .player_stats
+                .iter()
+                .filter(|s| teams.get(s.player_id.as_str()) == Some(team))
+                .filter_map(|s| s.economy.as_ref())
+                .collect();
+
+            if economies.is_empty() {
+                continue;
+            }
+
+            let players = economies.len() as u32;
+            let loadout_value: u32 = economies.iter().map(|e| e.loadout_value).sum();
+            let spent: u32 = economies.iter().map(|e| e.spent).sum();
+            let remaining: u32 = economies.iter().map(|e| e.remaining).sum();
+
+            let buy_type = thresholds.classify(round.round_num, loadout_value / players, remaining / players);
+            let loss_streak = loss_streaks.get(team).copied().unwrap_or(0);
+            let won = round.winning_team == *team;
+
+            let record = records.entry((*team, buy_type)).or_default();
+            record.0 += 1;
+            if won {
+                record.1 += 1;
+            }
+
+            round_teams.push(TeamRoundEconomy {
+                team_id: team.to_string().into(),
+                buy_type,
+                loadout_value,
+                spent,
+                remaining,
+                loss_streak,
+                loss_bonus: loss_bonus(loss_streak),
+                won,
+            });
+        }
+
+        if overtime {
+            rounds.push(RoundEconomy {
+                round: round.round_num,
+                teams: round_teams,
+            });
+            continue;
+        }
+
+        for team in team_ids.iter() {
+            let streak = loss_streaks.entry(team).or_default();
+            if round.winning_team == *team {
+                *streak = 0;
+            } else {
+                *streak += 1;
+            }
+
+            let max = max_loss_streaks.entry(team.to_string()).or_default();
+            *max = (*max).max(*streak);
+        }
+
+        if let (Some(winner), Some(loser)) = (round_teams.iter().find(|t| t.won), round_teams.iter().find(|t| !t.won)) {
+            if winner.buy_type.strength() < loser.buy_type.strength() {
+                swings.push(EconomySwing {
+                    round: round.round_num,
+                    winning_team: winner.team_id.clone(),
+                    winner_buy: winner.buy_type,
+                    loser_buy: loser.buy_type,
+                    loadout_difference: loser.loadout_value.saturating_sub(winner.loadout_value),
+                });
+            }
+        }
+
+        rounds.push(RoundEconomy {
+            round: round.round_num,
+            teams: round_teams,
+        });
+    }
+
+    let mut buy_types: Vec<BuyTypeRecord> = records
+        .into_iter()
+        .map(|((team_id, buy_type), (rounds, wins))| BuyTypeRecord {
+            team_id: team_id.to_string().into(),
+            buy_type,
+            rounds,
+            wins,
+            win_rate: percentage(wins, rounds),
+        })
+        .collect();
+    buy_types.sort_by_key(|r| (r.team_id.as_str().to_string(), r.buy_type.strength()));
+
+    EconomyReport {
+        rounds,
+        buy_types,
+        max_loss_streaks,
+        swings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH: &str = include_str!("../../tests/fixtures/match.json");
+
+    #[test]
+    fn test_classify() {
+        let thresholds = EconomyThresholds::default();
+
+        assert_eq!(thresholds.classify(12, 4000, 0), BuyType::Pistol);
+        assert_eq!(thresholds.classify(3, 800, 2000), BuyType::Eco);
+        assert_eq!(thresholds.classify(3, 2500, 200), BuyType::Force);
+        assert_eq!(thresholds.classify(3, 2500, 1500), BuyType::HalfBuy);
+        assert_eq!(thresholds.classify(3, 4500, 0), BuyType::FullBuy);
+
+        let thresholds = EconomyThresholds {
+            eco_max: 3000,
+            ..Default::default()
+        };
+
+        assert_eq!(thresholds.classify(3, 2500, 1500), BuyType::Eco);
+
+        let thresholds = EconomyThresholds::for_mode(GameMode::SpikeRush);
+        assert_eq!(thresholds.classify(3, 4500, 0), BuyType::Pistol);
+        assert_eq!(thresholds.classify(6, 800, 2000), BuyType::Overtime);
+
+        let thresholds = EconomyThresholds::default();
+        assert_eq!(thresholds.classify(23, 800, 2000), BuyType::Eco);
+        assert_eq!(thresholds.classify(24, 800, 2000), BuyType::Overtime);
+        assert_eq!(thresholds.classify(27, 4500, 0), BuyType::Overtime);
+    }
+
+    #[test]
+    fn test_economy_report() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let report = economy(&match_, &EconomyThresholds::default());
+
+        let buys: Vec<Vec<(&str, BuyType)>> = report
+            .rounds
+            .iter()
+            .map(|r| r.teams.iter().map(|t| (t.team_id.as_str(), t.buy_type)).collect())
+            .collect();
+
+        assert_eq!(buys[0], vec![("Blue", BuyType::Pistol), ("Red", BuyType::Pistol)]);
+        assert_eq!(buys[1], vec![("Blue", BuyType::FullBuy), ("Red", BuyType::FullBuy)]);
+        assert_eq!(buys[2], vec![("Blue", BuyType::FullBuy), ("Red", BuyType::HalfBuy)]);
+
+        let blue = &report.rounds[1].teams[0];
+        assert_eq!((blue.loss_streak, blue.loss_bonus), (1, 1900));
+
+        assert_eq!(report.max_loss_streaks["Red"], 2);
+        assert!(report.swings.is_empty());
+
+        let blue_full = report
+            .buy_types
+            .iter()
+            .find(|r| r.team_id == "Blue" && r.buy_type == BuyType::FullBuy)
+            .unwrap();
+        assert_eq!((blue_full.rounds, blue_full.wins), (2, 2));
+        assert_eq!(blue_full.win_rate, 100.0);
+    }
+
+    #[test]
+    fn test_overtime_rounds() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let thresholds = EconomyThresholds {
+            overtime_from: Some(1),
+            ..Default::default()
+        };
+        let report = economy(&match_, &thresholds);
+
+        for round in report.rounds[1..].iter() {
+            for team in round.teams.iter() {
+                assert_eq!(team.buy_type, BuyType::Overtime);
+                assert_eq!((team.loss_streak, team.loss_bonus), (0, 0));
+            }
+        }
+
+        assert_eq!(report.max_loss_streaks["Red"], 0);
+        assert_eq!(report.max_loss_streaks["Blue"], 1);
+        assert!(report.swings.is_empty());
+    }
+}
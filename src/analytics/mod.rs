@@ -0,0 +1,210 @@
+mod aggregate;
+mod duels;
+mod economy;
+mod scoreboard;
+mod sessions;
+mod sides;
+
+pub use aggregate::*;
+pub use duels::*;
+pub use economy::*;
+pub use scoreboard::*;
+pub use sessions::*;
+pub use sides::*;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::r#match::{Kill, Match};
+
+/// Kills can only be traded while the killer is still close by.
+pub const DEFAULT_TRADE_WINDOW: u32 = 5000;
+
+fn percentage(part: u32, total: u32) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+/// Each player's team ID.
+pub fn teams_by_player(match_: &Match) -> HashMap<&str, &str> {
+    match_
+        .players
+        .iter()
+        .map(|p| (p.id.as_str(), p.team_id.as_str()))
+        .collect()
+}
+
+/// Kills grouped by round, each round ordered by round time. Kills missing their round or round
+/// time can't be placed, so they are left out.
+pub fn kills_by_round(match_: &Match) -> BTreeMap<u32, Vec<&Kill>> {
+    let mut rounds: BTreeMap<u32, Vec<&Kill>> = BTreeMap::new();
+
+    for kill in match_.kills.iter().filter(|k| k.round_time.is_some()) {
+        if let Some(round) = kill.round {
+            rounds.entry(round).or_default().push(kill);
+        }
+    }
+
+    for kills in rounds.values_mut() {
+        kills.sort_by_key(|k| k.round_time);
+    }
+
+    rounds
+}
+
+/// Whether the killer was on the other team, so the death wasn't a suicide or team kill.
+pub fn is_enemy_kill(kill: &Kill, teams: &HashMap<&str, &str>) -> bool {
+    kill.killer != kill.victim && teams.get(kill.killer.as_str()) != teams.get(kill.victim.as_str())
+}
+
+/// The kill that avenged the death at `index` in `round_kills`, if a teammate of the victim got one
+/// within `window` milliseconds, and how long after the death it came. Suicides and team kills are
+/// never traded, nor are kills missing their round time.
+pub fn find_trade<'a>(index: usize, round_kills: &[&'a Kill], teams: &HashMap<&str, &str>, window: u32) -> Option<(&'a Kill, u32)> {
+    find_uncredited_trade(index, round_kills, teams, window, &HashSet::new()).map(|(i, delay)| (round_kills[i], delay))
+}
+
+/// Like `find_trade`, but skips the kills at the indices in `credited`, which already traded another
+/// death, and returns the index of the trade instead.
+pub fn find_uncredited_trade(index: usize, round_kills: &[&Kill], teams: &HashMap<&str, &str>, window: u32, credited: &HashSet<usize>) -> Option<(usize, u32)> {
+    let kill = round_kills[index];
+    let kill_time = kill.round_time?;
+    let victim_team = teams.get(kill.victim.as_str());
+
+    if !is_enemy_kill(kill, teams) {
+        return None;
+    }
+
+    round_kills.iter().enumerate().find_map(|(i, other)| {
+        let delay = other.round_time?.checked_sub(kill_time)?;
+
+        (i != index
+            && !credited.contains(&i)
+            && other.victim == kill.killer
+            && other.killer != kill.victim
+            && delay <= window
+            && teams.get(other.killer.as_str()) == victim_team)
+            .then_some((i, delay))
+    })
+}
+
+pub fn is_traded(index: usize, round_kills: &[&Kill], teams: &HashMap<&str, &str>, window: u32) -> bool {
+    find_trade(index, round_kills, teams, window).is_some()
+}
+
+/// Players still alive on each team, replayed kill by kill through a round.
+pub struct AliveTracker<'a> {
+    alive: HashMap<&'a str, HashSet<&'a str>>,
+}
+
+impl<'a> AliveTracker<'a> {
+    pub fn new(teams: &HashMap<&'a str, &'a str>) -> Self {
+        let mut alive: HashMap<&str, HashSet<&str>> = HashMap::new();
+
+        for (player, team) in teams.iter() {
+            alive.entry(*team).or_default().insert(*player);
+        }
+
+        Self { alive }
+    }
+
+    pub fn kill(&mut self, victim: &str) {
+        for players in self.alive.values_mut() {
+            players.remove(victim);
+        }
+    }
+
+    pub fn alive(&self, team: &str) -> usize {
+        self.alive.get(team).map_or(0, |players| players.len())
+    }
+
+    pub fn alive_players(&self, team: &str) -> Vec<&'a str> {
+        self.alive
+            .get(team)
+            .map(|players| players.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Players alive on every team other than `team`.
+    pub fn enemies_alive(&self, team: &str) -> usize {
+        self.alive
+            .iter()
+            .filter(|(t, _)| **t != team)
+            .map(|(_, players)| players.len())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH: &str = include_str!("../../tests/fixtures/match.json");
+
+    fn kill(killer: &str, victim: &str, round_time: u32) -> Kill {
+        serde_json::from_value(serde_json::json!({
+            "killer": killer,
+            "victim": victim,
+            "round": 0,
+            "roundTime": round_time,
+        }))
+        .unwrap()
+    }
+
+    fn teams() -> HashMap<&'static str, &'static str> {
+        HashMap::from([("a1", "Red"), ("a2", "Red"), ("b1", "Blue"), ("b2", "Blue")])
+    }
+
+    #[test]
+    fn test_find_trade() {
+        let kills = [kill("b1", "a1", 1000), kill("a2", "b1", 3000), kill("a2", "b2", 9000)];
+        let kills: Vec<&Kill> = kills.iter().collect();
+
+        let (trade, delay) = find_trade(0, &kills, &teams(), DEFAULT_TRADE_WINDOW).unwrap();
+        assert_eq!((trade.victim.as_str(), delay), ("b1", 2000));
+        assert!(!is_traded(1, &kills, &teams(), DEFAULT_TRADE_WINDOW));
+        assert!(!is_traded(0, &kills, &teams(), 1000));
+    }
+
+    #[test]
+    fn test_credited_trade_is_not_reused() {
+        let kills = [kill("b1", "a1", 1000), kill("b1", "b2", 1500), kill("a2", "b1", 3000)];
+        let kills: Vec<&Kill> = kills.iter().collect();
+
+        assert_eq!(find_uncredited_trade(0, &kills, &teams(), DEFAULT_TRADE_WINDOW, &HashSet::new()), Some((2, 2000)));
+        assert_eq!(find_uncredited_trade(0, &kills, &teams(), DEFAULT_TRADE_WINDOW, &HashSet::from([2])), None);
+        assert!(!is_enemy_kill(kills[1], &teams()));
+    }
+
+    #[test]
+    fn test_kills_by_round_skips_incomplete_kills() {
+        let mut match_: Match = serde_json::from_str(MATCH).unwrap();
+        match_.kills.push(kill("b1", "a1", 0));
+        match_.kills.last_mut().unwrap().round = None;
+        match_.kills.push(kill("b1", "a2", 0));
+        match_.kills.last_mut().unwrap().round_time = None;
+
+        let rounds = kills_by_round(&match_);
+
+        assert_eq!(rounds.values().map(Vec::len).sum::<usize>(), 8);
+        assert!(rounds[&0].iter().all(|k| k.round_time.is_some()));
+    }
+
+    #[test]
+    fn test_suicide_is_not_traded() {
+        let kills = [kill("a1", "a1", 1000), kill("b1", "a1", 1000)];
+        let kills: Vec<&Kill> = kills.iter().collect();
+
+        assert!(!is_traded(0, &kills, &teams(), DEFAULT_TRADE_WINDOW));
+    }
+
+    #[test]
+    fn test_team_kill_is_not_traded() {
+        let kills = [kill("a1", "a2", 1000), kill("b1", "a1", 2000), kill("a2", "a1", 2000)];
+        let kills: Vec<&Kill> = kills.iter().collect();
+
+        assert!(!is_traded(0, &kills, &teams(), DEFAULT_TRADE_WINDOW));
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::{is_traded, kills_by_round, percentage, teams_by_player, AliveTracker, DEFAULT_TRADE_WINDOW};
+use crate::r#match::{AgentId, Match, PlayerId, TeamColor};
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct MultiKills {
+    pub doubles: u32,
+    pub triples: u32,
+    pub quadras: u32,
+    pub aces: u32,
+}
+
+/// A round where the player was the last one alive on their team.
+#[derive(Debug, Clone, Serialize)]
+pub struct Clutch {
+    pub round: u32,
+    pub opponents: usize,
+    pub won: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerMetrics {
+    pub player_id: PlayerId,
+    pub ign: String,
+    pub tagline: String,
+    pub team_id: TeamColor,
+    pub agent_id: AgentId,
+
+    pub rounds: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+
+    /// Average combat score
+    pub acs: f64,
+    /// Average damage per round
+    pub adr: f64,
+    pub kd: f64,
+    /// Percentage of rounds with a kill, assist, survival or traded death
+    pub kast: f64,
+    pub headshot_percentage: f64,
+
+    pub first_bloods: u32,
+    pub first_deaths: u32,
+    pub multi_kills: MultiKills,
+    pub clutches: Vec<Clutch>,
+    pub plants: u32,
+    pub defuses: u32,
+}
+
+#[derive(Default)]
+struct Totals {
+    damage: u32,
+    headshots: u32,
+    shots: u32,
+    kast_rounds: u32,
+    first_bloods: u32,
+    first_deaths: u32,
+    multi_kills: MultiKills,
+    clutches: Vec<Clutch>,
+    plants: u32,
+    defuses: u32,
+}
+
+/// Per-player scoreboard for a match, ordered by ACS.
+pub fn scoreboard(match_: &Match) -> Vec<PlayerMetrics> {
+    let teams = teams_by_player(match_);
+    let rounds = kills_by_round(match_);
+    let round_count = match_.round_results.len() as u32;
+
+    let mut team_sizes: HashMap<&str, usize> = HashMap::new();
+    for team in teams.values() {
+        *team_sizes.entry(*team).or_default() += 1;
+    }
+
+    let mut totals: HashMap<&str, Totals> = teams.keys().map(|p| (*p, Totals::default())).collect();
+
+    for round in match_.round_results.iter() {
+        let kills = rounds.get(&round.round_num).cloned().unwrap_or_default();
+
+        for stats in round.player_stats.iter() {
+            if let Some(total) = totals.get_mut(stats.player_id.as_str()) {
+                for damage in stats.damage.iter() {
+                    total.damage += damage.damage;
+                    total.headshots += damage.headshots;
+                    total.shots += damage.headshots + damage.bodyshots + damage.legshots;
+                }
+            }
+        }
+
+        if let Some(first) = kills.first() {
+            if let Some(total) = totals.get_mut(first.killer.as_str()) {
+                total.first_bloods += 1;
+            }
+            if let Some(total) = totals.get_mut(first.victim.as_str()) {
+                total.first_deaths += 1;
+            }
+        }
+
+        let mut round_kills: HashMap<&str, u32> = HashMap::new();
+        for kill in kills.iter() {
+            *round_kills.entry(kill.killer.as_str()).or_default() += 1;
+        }
+
+        for (player, count) in round_kills {
+            if let Some(total) = totals.get_mut(player) {
+                match count {
+                    2 => total.multi_kills.doubles += 1,
+                    3 => total.multi_kills.triples += 1,
+                    4 => total.multi_kills.quadras += 1,
+                    c if c >= 5 => total.multi_kills.aces += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        for (player, total) in totals.iter_mut() {
+            let killed_or_assisted = kills
+                .iter()
+                .any(|k| k.killer == *player || k.assistants.iter().any(|a| a == player));
+            let death = kills.iter().position(|k| k.victim == *player);
+            let survived_or_traded = match death {
+                None => true,
+                Some(death) => is_traded(death, &kills, &teams, DEFAULT_TRADE_WINDOW),
+            };
+
+            if killed_or_assisted || survived_or_traded {
+                total.kast_rounds += 1;
+            }
+        }
+
+        let mut alive = AliveTracker::new(&teams);
+        let mut clutching: HashMap<&str, usize> = HashMap::new();
+
+        for kill in kills.iter() {
+            alive.kill(&kill.victim);
+
+            for (team, size) in team_sizes.iter() {
+                let opponents = alive.enemies_alive(team);
+
+                if *size > 1 && alive.alive(team) == 1 && opponents > 0 {
+                    clutching.entry(alive.alive_players(team)[0]).or_insert(opponents);
+                }
+            }
+        }
+
+        for (player, opponents) in clutching {
+            if let Some(total) = totals.get_mut(player) {
+                total.clutches.push(Clutch {
+                    round: round.round_num,
+                    opponents,
+                    won: teams.get(player) == Some(&round.winning_team.as_str()),
+                });
+            }
+        }
+
+        if let Some(plant) = round.plant.as_ref() {
+            if let Some(total) = totals.get_mut(plant.planter.as_str()) {
+                total.plants += 1;
+            }
+        }
+
+        if let Some(defuse) = round.defuse.as_ref() {
+            if let Some(total) = totals.get_mut(defuse.defuser.as_str()) {
+                total.defuses += 1;
+            }
+        }
+    }
+
+    let mut scoreboard: Vec<PlayerMetrics> = match_
+        .players
+        .iter()
+        .map(|player| {
+            let total = totals.remove(player.id.as_str()).unwrap_or_default();
+            let rounds = if player.stats.rounds_played > 0 { player.stats.rounds_played } else { round_count };
+            let per_round = |value: u32| if rounds == 0 { 0.0 } else { value as f64 / rounds as f64 };
+
+            PlayerMetrics {
+                player_id: player.id.clone(),
+                ign: player.ign.clone(),
+                tagline: player.tagline.clone(),
+                team_id: player.team_id.clone(),
+                agent_id: player.agent_id.clone(),
+                rounds,
+                kills: player.stats.kills,
+                deaths: player.stats.deaths,
+                assists: player.stats.assists,
+                acs: per_round(player.stats.score),
+                adr: per_round(total.damage),
+                kd: player.stats.kills as f64 / player.stats.deaths.max(1) as f64,
+                kast: percentage(total.kast_rounds, round_count),
+                headshot_percentage: percentage(total.headshots, total.shots),
+                first_bloods: total.first_bloods,
+                first_deaths: total.first_deaths,
+                multi_kills: total.multi_kills,
+                clutches: total.clutches,
+                plants: total.plants,
+                defuses: total.defuses,
+            }
+        })
+        .collect();
+
+    scoreboard.sort_by(|a, b| b.acs.total_cmp(&a.acs));
+
+    scoreboard
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH: &str = include_str!("../../tests/fixtures/match.json");
+
+    fn metrics<'a>(scoreboard: &'a [PlayerMetrics], ign: &str) -> &'a PlayerMetrics {
+        scoreboard.iter().find(|p| p.ign == ign).unwrap()
+    }
+
+    #[test]
+    fn test_scoreboard() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let scoreboard = scoreboard(&match_);
+
+        assert_eq!(scoreboard[0].ign, "Delta");
+
+        let alpha = metrics(&scoreboard, "Alpha");
+        assert_eq!(alpha.acs, 170.0);
+        assert!((alpha.adr - 380.0 / 3.0).abs() < 1e-9);
+        assert_eq!(alpha.kd, 1.0);
+        assert!((alpha.kast - 100.0 / 3.0).abs() < 1e-9);
+        assert!((alpha.headshot_percentage - 100.0 / 6.0).abs() < 1e-9);
+        assert_eq!(alpha.first_bloods, 1);
+        assert_eq!(alpha.first_deaths, 1);
+        assert_eq!(alpha.multi_kills.doubles, 1);
+        assert_eq!(alpha.plants, 1);
+
+        let charlie = metrics(&scoreboard, "Charlie");
+        assert!((charlie.kast - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(charlie.first_deaths, 2);
+
+        let delta = metrics(&scoreboard, "Delta");
+        assert_eq!(delta.kast, 100.0);
+        assert_eq!(delta.defuses, 1);
+    }
+
+    #[test]
+    fn test_clutches() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let scoreboard = scoreboard(&match_);
+
+        let alpha = metrics(&scoreboard, "Alpha");
+        assert_eq!(alpha.clutches.len(), 1);
+        assert_eq!((alpha.clutches[0].round, alpha.clutches[0].opponents, alpha.clutches[0].won), (0, 1, true));
+
+        let delta = metrics(&scoreboard, "Delta");
+        let won: Vec<(u32, usize)> = delta.clutches.iter().filter(|c| c.won).map(|c| (c.round, c.opponents)).collect();
+        assert_eq!(won, vec![(2, 2)]);
+        assert_eq!(delta.clutches.len(), 2);
+
+        let bravo = metrics(&scoreboard, "Bravo");
+        assert!(bravo.clutches.iter().all(|c| !c.won));
+    }
+}
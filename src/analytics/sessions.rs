@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::{aggregate, StatLine, StatsFilter};
+use crate::history::MatchHistoryEntry;
+use crate::mmr::CompetitiveUpdate;
+use crate::queue::QueueId;
+use crate::r#match::Match;
+
+const MINUTE_MILLIS: u64 = 60 * 1000;
+
+/// A new session starts after this long without playing.
+pub const DEFAULT_IDLE_GAP: u64 = 30 * MINUTE_MILLIS;
+
+/// Typical length of a match in the queue, for entries whose details weren't loaded.
+fn estimated_length(queue: &QueueId) -> u64 {
+    let minutes = match queue {
+        QueueId::Deathmatch | QueueId::Snowball => 10,
+        QueueId::SpikeRush | QueueId::Escalation => 15,
+        QueueId::Swiftplay => 20,
+        _ => 35,
+    };
+
+    minutes * MINUTE_MILLIS
+}
+
+fn game_length(entry: &MatchHistoryEntry) -> u64 {
+    entry
+        .game_data
+        .as_ref()
+        .map_or_else(|| estimated_length(&entry.queue_id), |m| m.match_info.game_length)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Streak {
+    pub outcome: Outcome,
+    pub length: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionMatch {
+    pub match_id: String,
+    pub start_time: u64,
+    /// None when the match details weren't loaded
+    pub outcome: Option<Outcome>,
+    /// Only known for competitive matches
+    pub rr_earned: Option<i32>,
+}
+
+/// How the first half of a session's matches went compared to the rest.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionTrend {
+    pub early: StatLine,
+    pub late: StatLine,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Session {
+    pub start_time: u64,
+    /// When the last match ended
+    pub end_time: u64,
+    pub matches: Vec<SessionMatch>,
+    pub wins: u32,
+    pub losses: u32,
+    pub rr_change: i32,
+    pub longest_win_streak: u32,
+    pub longest_loss_streak: u32,
+    /// Missing for sessions with fewer than two matches with details
+    pub trend: Option<SessionTrend>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionReport {
+    /// Oldest first
+    pub sessions: Vec<Session>,
+    pub longest_win_streak: u32,
+    pub longest_loss_streak: u32,
+    /// The streak the player is on as of their latest match
+    pub current_streak: Option<Streak>,
+}
+
+fn outcome(match_: &Match, player_id: &str) -> Option<Outcome> {
+    let team = match_.team_of(player_id)?;
+
+    if match_.teams.iter().any(|t| t.team_id == *team && t.won) {
+        Some(Outcome::Win)
+    } else if match_.teams.iter().any(|t| t.won) {
+        Some(Outcome::Loss)
+    } else {
+        Some(Outcome::Draw)
+    }
+}
+
+/// Longest win and loss streaks. Draws break streaks, matches without an outcome are skipped.
+fn streaks(outcomes: impl IntoIterator<Item = Outcome>) -> (u32, u32, Option<Streak>) {
+    let mut longest_win = 0;
+    let mut longest_loss = 0;
+    let mut current: Option<Streak> = None;
+
+    for outcome in outcomes {
+        current = match current {
+            Some(streak) if streak.outcome == outcome => Some(Streak { length: streak.length + 1, ..streak }),
+            _ => Some(Streak { outcome, length: 1 }),
+        };
+
+        if let Some(streak) = current {
+            match streak.outcome {
+                Outcome::Win => longest_win = longest_win.max(streak.length),
+                Outcome::Loss => longest_loss = longest_loss.max(streak.length),
+                Outcome::Draw => {}
+            }
+        }
+    }
+
+    (longest_win, longest_loss, current)
+}
+
+fn session(entries: &[&MatchHistoryEntry], player_id: &str, rr_earned: &HashMap<&str, i32>) -> Session {
+    let matches: Vec<SessionMatch> = entries
+        .iter()
+        .map(|entry| SessionMatch {
+            match_id: entry.match_id.clone(),
+            start_time: entry.start_time,
+            outcome: entry.game_data.as_ref().and_then(|m| outcome(m, player_id)),
+            rr_earned: rr_earned.get(entry.match_id.as_str()).copied(),
+        })
+        .collect();
+
+    let (longest_win_streak, longest_loss_streak, _) = streaks(matches.iter().filter_map(|m| m.outcome));
+
+    let details: Vec<&Match> = entries.iter().filter_map(|entry| entry.game_data.as_ref()).collect();
+    let trend = (details.len() >= 2).then(|| {
+        let (early, late) = details.split_at(details.len() / 2);
+
+        SessionTrend {
+            early: aggregate(early.iter().copied(), player_id, &StatsFilter::default()).overall,
+            late: aggregate(late.iter().copied(), player_id, &StatsFilter::default()).overall,
+        }
+    });
+
+    let last = entries[entries.len() - 1];
+
+    Session {
+        start_time: entries[0].start_time,
+        end_time: last.start_time + game_length(last),
+        wins: matches.iter().filter(|m| m.outcome == Some(Outcome::Win)).count() as u32,
+        losses: matches.iter().filter(|m| m.outcome == Some(Outcome::Loss)).count() as u32,
+        rr_change: matches.iter().filter_map(|m| m.rr_earned).sum(),
+        matches,
+        longest_win_streak,
+        longest_loss_streak,
+        trend,
+    }
+}
+
+/// Groups a player's history into sessions separated by at least `idle_gap` milliseconds between
+/// the end of one match and the start of the next. Matches without details are assumed to have
+/// lasted as long as is typical for their queue. RR comes from the competitive updates given.
+pub fn sessions(history: &[MatchHistoryEntry], player_id: &str, updates: &[CompetitiveUpdate], idle_gap: u64) -> SessionReport {
+    let rr_earned: HashMap<&str, i32> = updates.iter().map(|u| (u.match_id.as_str(), u.rr_earned)).collect();
+
+    let mut entries: Vec<&MatchHistoryEntry> = history.iter().collect();
+    entries.sort_by_key(|entry| entry.start_time);
+
+    let mut groups: Vec<Vec<&MatchHistoryEntry>> = Vec::new();
+    let mut last_end: Option<u64> = None;
+
+    for entry in entries {
+        match (groups.last_mut(), last_end) {
+            (Some(group), Some(end)) if entry.start_time < end.saturating_add(idle_gap) => group.push(entry),
+            _ => groups.push(vec![entry]),
+        }
+
+        last_end = Some(entry.start_time + game_length(entry));
+    }
+
+    let sessions: Vec<Session> = groups.iter().map(|group| session(group, player_id, &rr_earned)).collect();
+
+    let (longest_win_streak, longest_loss_streak, current_streak) =
+        streaks(sessions.iter().flat_map(|s| s.matches.iter()).filter_map(|m| m.outcome));
+
+    SessionReport {
+        sessions,
+        longest_win_streak,
+        longest_loss_streak,
+        current_streak,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::MatchType;
+    use crate::queue::QueueId;
+
+    const MATCH: &str = include_str!("../../tests/fixtures/match.json");
+
+    const MINUTE: u64 = MINUTE_MILLIS;
+
+    fn entry(match_id: &str, start_time: u64) -> MatchHistoryEntry {
+        MatchHistoryEntry {
+            match_id: match_id.to_string(),
+            match_type: MatchType::Competitive,
+            queue_id: QueueId::Competitive,
+            start_time,
+            game_data: None,
+        }
+    }
+
+    fn update(match_id: &str, rr_earned: i32) -> CompetitiveUpdate {
+        CompetitiveUpdate {
+            match_id: match_id.to_string(),
+            map_id: String::new(),
+            season_id: String::new(),
+            start_time: 0,
+            tier_before: 0,
+            tier_after: 0,
+            rr_before: 0,
+            rr_after: 0,
+            rr_earned,
+            performance_bonus: 0,
+            afk_penalty: 0,
+        }
+    }
+
+    #[test]
+    fn test_streaks() {
+        use Outcome::*;
+
+        let (win, loss, current) = streaks([Win, Win, Loss, Draw, Loss, Loss, Loss, Win]);
+
+        assert_eq!((win, loss), (2, 3));
+        assert_eq!(current, Some(Streak { outcome: Win, length: 1 }));
+        assert_eq!(streaks([]), (0, 0, None));
+    }
+
+    #[test]
+    fn test_sessions() {
+        let mut with_details = entry("b", 20 * MINUTE);
+        with_details.game_data = Some(serde_json::from_str(MATCH).unwrap());
+
+        let history = vec![
+            entry("d", 10 * 60 * MINUTE),
+            entry("a", 0),
+            with_details,
+            entry("c", 20 * 60 * MINUTE),
+        ];
+        let updates = vec![update("a", 18), update("b", -15), update("d", 21)];
+
+        let report = sessions(&history, "00000000-0000-0000-0000-000000000004", &updates, DEFAULT_IDLE_GAP);
+
+        assert_eq!(report.sessions.len(), 3);
+
+        let first = &report.sessions[0];
+        assert_eq!(first.matches.len(), 2);
+        assert_eq!(first.rr_change, 3);
+        assert_eq!(first.wins, 1);
+        assert_eq!(first.matches[0].outcome, None);
+        assert!(first.trend.is_none());
+        assert_eq!(first.end_time, 20 * MINUTE + history[2].game_data.as_ref().unwrap().match_info.game_length);
+
+        assert_eq!(report.sessions[1].rr_change, 21);
+        assert_eq!(report.current_streak, Some(Streak { outcome: Outcome::Win, length: 1 }));
+    }
+
+    #[test]
+    fn test_sessions_without_details() {
+        // Back to back competitive matches, each starting 40 minutes after the last
+        let history: Vec<MatchHistoryEntry> = (0..3).map(|i| entry(&i.to_string(), i * 40 * MINUTE)).collect();
+
+        let report = sessions(&history, "00000000-0000-0000-0000-000000000004", &[], 10 * MINUTE);
+
+        assert_eq!(report.sessions.len(), 1);
+        assert_eq!(report.sessions[0].matches.len(), 3);
+        assert_eq!(report.sessions[0].end_time, 80 * MINUTE + estimated_length(&QueueId::Competitive));
+
+        let mut deathmatch = entry("3", 120 * MINUTE);
+        deathmatch.queue_id = QueueId::Deathmatch;
+
+        let report = sessions(&[deathmatch], "00000000-0000-0000-0000-000000000004", &[], 5 * MINUTE);
+        assert_eq!(report.sessions[0].end_time, 130 * MINUTE);
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::r#match::{Match, PlayerId, Side, TeamColor};
+
+/// A run of consecutive rounds where the same team attacked.
+#[derive(Debug, Clone, Serialize)]
+pub struct SideSwap {
+    pub first_round: u32,
+    pub last_round: u32,
+    pub attacking_team: TeamColor,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SideRecord {
+    pub rounds: u32,
+    pub wins: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamSides {
+    pub team_id: TeamColor,
+    pub attack: SideRecord,
+    pub defense: SideRecord,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SideStats {
+    pub rounds: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub damage: u32,
+    pub score: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerSides {
+    pub player_id: PlayerId,
+    pub ign: String,
+    pub tagline: String,
+    pub team_id: TeamColor,
+    pub attack: SideStats,
+    pub defense: SideStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SideReport {
+    pub halves: Vec<SideSwap>,
+    pub teams: Vec<TeamSides>,
+    pub players: Vec<PlayerSides>,
+}
+
+fn by_side<'a, T>(side: Side, attack: &'a mut T, defense: &'a mut T) -> &'a mut T {
+    match side {
+        Side::Attack => attack,
+        Side::Defense => defense,
+    }
+}
+
+/// Rounds won by each team and player stats, split by attack and defense.
+pub fn sides(match_: &Match) -> SideReport {
+    let mut halves: Vec<SideSwap> = Vec::new();
+
+    for round in match_.round_results.iter() {
+        let attacking_team = match_.attacking_team(round.round_num);
+
+        match halves.last_mut() {
+            Some(half) if half.attacking_team == attacking_team => half.last_round = round.round_num,
+            _ => halves.push(SideSwap {
+                first_round: round.round_num,
+                last_round: round.round_num,
+                attacking_team,
+            }),
+        }
+    }
+
+    let mut teams: Vec<TeamSides> = match_
+        .teams
+        .iter()
+        .map(|team| TeamSides {
+            team_id: team.team_id.clone(),
+            attack: SideRecord::default(),
+            defense: SideRecord::default(),
+        })
+        .collect();
+
+    for round in match_.round_results.iter() {
+        for team in teams.iter_mut() {
+            let record = by_side(match_.side_of(&team.team_id, round.round_num), &mut team.attack, &mut team.defense);
+
+            record.rounds += 1;
+            if round.winning_team == team.team_id {
+                record.wins += 1;
+            }
+        }
+    }
+
+    let mut players: Vec<PlayerSides> = match_
+        .players
+        .iter()
+        .map(|player| PlayerSides {
+            player_id: player.id.clone(),
+            ign: player.ign.clone(),
+            tagline: player.tagline.clone(),
+            team_id: player.team_id.clone(),
+            attack: SideStats::default(),
+            defense: SideStats::default(),
+        })
+        .collect();
+
+    let index: HashMap<PlayerId, usize> = players
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.player_id.clone(), i))
+        .collect();
+
+    for round in match_.round_results.iter() {
+        for player in players.iter_mut() {
+            let side = match_.side_of(&player.team_id, round.round_num);
+            let stats = by_side(side, &mut player.attack, &mut player.defense);
+
+            if let Some(round_stats) = round.stats_for(&player.player_id) {
+                stats.rounds += 1;
+                stats.score += round_stats.score;
+                stats.damage += round_stats.damage.iter().map(|d| d.damage).sum::<u32>();
+            }
+        }
+    }
+
+    for kill in match_.kills.iter() {
+        // Without a round there is no telling which side the players were on
+        let Some(round) = kill.round else {
+            continue;
+        };
+
+        let mut record = |player_id: &str, update: fn(&mut SideStats)| {
+            if let Some(player) = index.get(player_id).map(|i| &mut players[*i]) {
+                let side = match_.side_of(&player.team_id, round);
+                update(by_side(side, &mut player.attack, &mut player.defense));
+            }
+        };
+
+        record(&kill.killer, |stats| stats.kills += 1);
+        record(&kill.victim, |stats| stats.deaths += 1);
+
+        for assistant in kill.assistants.iter() {
+            record(assistant, |stats| stats.assists += 1);
+        }
+    }
+
+    SideReport {
+        halves,
+        teams,
+        players,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH: &str = include_str!("../../tests/fixtures/match.json");
+
+    #[test]
+    fn test_sides() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let report = sides(&match_);
+
+        assert_eq!(report.halves.len(), 1);
+        assert_eq!(report.halves[0].attacking_team, TeamColor::Red);
+        assert_eq!(report.halves[0].last_round, 2);
+
+        let blue = report.teams.iter().find(|t| t.team_id == TeamColor::Blue).unwrap();
+        assert_eq!((blue.defense.rounds, blue.defense.wins), (3, 2));
+        assert_eq!(blue.attack.rounds, 0);
+
+        let alpha = &report.players[0];
+        assert_eq!((alpha.attack.kills, alpha.attack.deaths), (2, 2));
+        assert_eq!(alpha.attack.damage, 380);
+        assert_eq!(alpha.defense.rounds, 0);
+
+        let charlie = &report.players[2];
+        assert_eq!((charlie.defense.kills, charlie.defense.assists), (1, 1));
+    }
+}
@@ -1,16 +1,12 @@
 use std::collections::HashMap;
 
-use anyhow::{Error, Result};
-use rocket::http::Status;
-use rocket::request::FromRequest;
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
 use url::Url;
 
-use rocket::request::Outcome;
-
-use crate::{AuthRequest, hashmap};
+use crate::hashmap;
 use crate::requests::*;
 
 #[derive(Error, Debug)]
@@ -49,14 +45,14 @@ pub struct AuthData {
 // }
 
 impl AuthData {
-    pub fn get_auth_headers<'a>(&self) -> HashMap<&str, String> {
+    pub fn get_auth_headers(&self) -> HashMap<&str, String> {
         let mut headers = HashMap::new();
 
         headers.insert("Authorization", format!("Bearer {}", self.access_token));
 
         headers.insert(
             "X-Riot-Entitlements-JWT",
-            format!("{}", self.entitlements_token),
+            self.entitlements_token.to_string(),
         );
 
         headers
@@ -64,10 +60,10 @@ impl AuthData {
 }
 
 pub async fn authenticate(client: &Client, username: &str, password: &str) -> Result<AuthData> {
-    let asid = handshake(&client).await?;
-    let (access_token, expires_in, id_token) = login(&client, &asid, username, password).await?;
-    let entitlements_token = get_entitlements(&client, &asid, &access_token).await?;
-    let (user_id, ign, tagline) = get_user_info(&client, &access_token).await?;
+    let asid = handshake(client).await?;
+    let (access_token, expires_in, id_token) = login(client, &asid, username, password).await?;
+    let entitlements_token = get_entitlements(client, &asid, &access_token).await?;
+    let (user_id, ign, tagline) = get_user_info(client, &access_token).await?;
 
     Ok(AuthData {
         access_token,
@@ -97,8 +93,7 @@ pub async fn handshake(client: &Client) -> Result<String> {
     let asid = headers
         .get_all("set-cookie")
         .iter()
-        .filter(|h| h.to_str().unwrap().contains("asid"))
-        .next()
+        .find(|h| h.to_str().unwrap().contains("asid"))
         .expect("No asid cookie found")
         .to_str()
         .expect("Failed to convert asid cookie to str")
@@ -144,24 +139,20 @@ pub async fn login(
 
                 let pairs = parsed.query_pairs();
 
-                let access_token: String;
-                let expires_in: usize;
-                let id_token: String;
-
-                match pairs.filter(|(k, _)| k == "access_token").next() {
-                    Some((_, v)) => access_token = v.to_string(),
+                let access_token = match pairs.clone().find(|(k, _)| k == "access_token") {
+                    Some((_, v)) => v.to_string(),
                     None => return Err(AuthError::ParseError.into()),
-                }
+                };
 
-                match pairs.filter(|(k, _)| k == "expires_in").next() {
-                    Some((_, v)) => expires_in = v.parse().unwrap(),
+                let expires_in: usize = match pairs.clone().find(|(k, _)| k == "expires_in") {
+                    Some((_, v)) => v.parse().unwrap(),
                     None => return Err(AuthError::ParseError.into()),
-                }
+                };
 
-                match pairs.filter(|(k, _)| k == "id_token").next() {
-                    Some((_, v)) => id_token = v.to_string(),
+                let id_token = match pairs.clone().find(|(k, _)| k == "id_token") {
+                    Some((_, v)) => v.to_string(),
                     None => return Err(AuthError::ParseError.into()),
-                }
+                };
 
                 return Ok((access_token, expires_in, id_token));
             }
@@ -1,11 +1,68 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// Environment variable holding a base64 encoded platform to send instead of the default.
+pub const CLIENT_PLATFORM_VAR: &str = "VALORANT_CLIENT_PLATFORM";
+
+/// Platform descriptor sent to Riot as the base64 encoded `X-Riot-ClientPlatform` header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientPlatform {
+    #[serde(rename = "platformType")]
+    pub platform_type: String,
+
+    #[serde(rename = "platformOS")]
+    pub os: String,
+
+    #[serde(rename = "platformOSVersion")]
+    pub os_version: String,
+
+    #[serde(rename = "platformChipset")]
+    pub chipset: String,
+}
+
+impl Default for ClientPlatform {
+    fn default() -> Self {
+        Self {
+            platform_type: "PC".to_string(),
+            os: "Windows".to_string(),
+            os_version: "10.0.19042.1.256.64bit".to_string(),
+            chipset: "Unknown".to_string(),
+        }
+    }
+}
+
+impl ClientPlatform {
+    /// The platform from `VALORANT_CLIENT_PLATFORM` if it is set, otherwise the default.
+    pub fn configured() -> Result<Self> {
+        Self::from_setting(std::env::var(CLIENT_PLATFORM_VAR).ok().as_deref())
+    }
+
+    fn from_setting(encoded: Option<&str>) -> Result<Self> {
+        match encoded {
+            Some(encoded) => Self::decode(encoded)
+                .map_err(|err| anyhow::anyhow!("invalid {}: {}", CLIENT_PLATFORM_VAR, err)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn encode(&self) -> Result<String> {
+        Ok(base64::encode(serde_json::to_string(self)?))
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self> {
+        Ok(serde_json::from_slice(&base64::decode(encoded)?)?)
+    }
+}
+
+/// Client headers for the configured platform, see `ClientPlatform::configured`.
 pub async fn get_client_headers() -> Result<HashMap<String, String>> {
-    const CLIENT_PLATFORM: &str = "ew0KCSJwbGF0Zm9ybVR5cGUiOiAiUEMiLA0KCSJwbGF0Zm9ybU9TIjogIldpbmRvd3MiLA0KCSJwbGF0Zm9ybU9TVmVyc2lvbiI6ICIxMC4wLjE5MDQyLjEuMjU2LjY0Yml0IiwNCgkicGxhdGZvcm1DaGlwc2V0IjogIlVua25vd24iDQp9";
+    get_client_headers_for(&ClientPlatform::configured()?).await
+}
 
+pub async fn get_client_headers_for(platform: &ClientPlatform) -> Result<HashMap<String, String>> {
     let version = reqwest::get("https://valorant-api.com/v1/version")
         .await?
         .json::<Value>()
@@ -20,10 +77,7 @@ pub async fn get_client_headers() -> Result<HashMap<String, String>> {
 
     headers.insert("X-Riot-ClientVersion".parse().unwrap(), version);
 
-    headers.insert(
-        "X-Riot-ClientPlatform".parse().unwrap(),
-        CLIENT_PLATFORM.parse().unwrap(),
-    );
+    headers.insert("X-Riot-ClientPlatform".parse().unwrap(), platform.encode()?);
 
     Ok(headers)
 }
@@ -39,4 +93,39 @@ mod tests {
         assert!(headers.contains_key("X-Riot-ClientVersion"));
         assert!(headers.contains_key("X-Riot-ClientPlatform"));
     }
+
+    #[test]
+    fn test_client_platform_round_trip() {
+        let platform = ClientPlatform {
+            os_version: "10.0.22621.1.256.64bit".to_string(),
+            ..Default::default()
+        };
+
+        let encoded = platform.encode().unwrap();
+
+        assert_eq!(ClientPlatform::decode(&encoded).unwrap(), platform);
+    }
+
+    #[test]
+    fn test_configured_client_platform() {
+        let platform = ClientPlatform {
+            platform_type: "PC".to_string(),
+            os: "Windows".to_string(),
+            os_version: "10.0.22621.1.256.64bit".to_string(),
+            chipset: "Unknown".to_string(),
+        };
+
+        let encoded = platform.encode().unwrap();
+
+        assert_eq!(ClientPlatform::from_setting(Some(&encoded)).unwrap(), platform);
+        assert!(ClientPlatform::from_setting(Some("not base64")).is_err());
+        assert_eq!(ClientPlatform::from_setting(None).unwrap(), ClientPlatform::default());
+    }
+
+    #[test]
+    fn test_decode_legacy_client_platform() {
+        const LEGACY: &str = "ew0KCSJwbGF0Zm9ybVR5cGUiOiAiUEMiLA0KCSJwbGF0Zm9ybU9TIjogIldpbmRvd3MiLA0KCSJwbGF0Zm9ybU9TVmVyc2lvbiI6ICIxMC4wLjE5MDQyLjEuMjU2LjY0Yml0IiwNCgkicGxhdGZvcm1DaGlwc2V0IjogIlVua25vd24iDQp9";
+
+        assert_eq!(ClientPlatform::decode(LEGACY).unwrap(), ClientPlatform::default());
+    }
 }
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+
+use crate::requests::*;
+
+/// Content tables published by valorant-api.com, keyed by the IDs Riot uses in its own responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentType {
+    Weapons,
+    Skins,
+    SkinLevels,
+    SkinChromas,
+    BuddyLevels,
+    Sprays,
+    PlayerCards,
+    PlayerTitles,
+    LevelBorders,
+    Agents,
+    Maps,
+    Seasons,
+}
+
+impl ContentType {
+    fn path(&self) -> &'static str {
+        match self {
+            ContentType::Weapons => "weapons",
+            ContentType::Skins => "weapons/skins",
+            ContentType::SkinLevels => "weapons/skinlevels",
+            ContentType::SkinChromas => "weapons/skinchromas",
+            ContentType::BuddyLevels => "buddies/levels",
+            ContentType::Sprays => "sprays",
+            ContentType::PlayerCards => "playercards",
+            ContentType::PlayerTitles => "playertitles",
+            ContentType::LevelBorders => "levelborders",
+            ContentType::Agents => "agents",
+            ContentType::Maps => "maps",
+            ContentType::Seasons => "seasons",
+        }
+    }
+}
+
+/// Display names for content IDs.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    names: HashMap<String, String>,
+    parents: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub async fn fetch(client: &Client, content_types: &[ContentType]) -> Result<Catalog> {
+        let mut catalog = Catalog::default();
+
+        for content_type in content_types {
+            for item in get_content::<response::Content>(client, *content_type).await? {
+                if let Some(display_name) = item.display_name {
+                    catalog.insert(&item.uuid, &display_name);
+                }
+
+                if let Some(parent_uuid) = item.parent_uuid {
+                    catalog.insert_parent(&item.uuid, &parent_uuid);
+                }
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    pub fn insert(&mut self, id: &str, name: &str) {
+        self.names.insert(id.to_lowercase(), name.to_string());
+    }
+
+    pub fn insert_parent(&mut self, id: &str, parent_id: &str) {
+        self.parents.insert(id.to_lowercase(), parent_id.to_lowercase());
+    }
+
+    pub fn name(&self, id: &str) -> Option<String> {
+        self.names.get(&id.to_lowercase()).cloned()
+    }
+
+    /// Name prefixed with its parent's name, e.g. `EPISODE 5 // ACT III` for an act.
+    pub fn qualified_name(&self, id: &str) -> Option<String> {
+        let name = self.name(id)?;
+
+        match self.parents.get(&id.to_lowercase()).and_then(|parent| self.name(parent)) {
+            Some(parent_name) => Some(format!("{} // {}", parent_name, name)),
+            None => Some(name),
+        }
+    }
+}
+
+pub async fn get_content<T: DeserializeOwned>(client: &Client, content_type: ContentType) -> Result<Vec<T>> {
+    let url = format!("https://valorant-api.com/v1/{}", content_type.path());
+
+    let req = Request::new(client, url, Method::GET);
+
+    let res = req.send().await?;
+
+    let data: response::ContentList<T> = res.json().await?;
+
+    Ok(data.data)
+}
+
+pub mod response {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct ContentList<T> {
+        pub data: Vec<T>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Content {
+        pub uuid: String,
+
+        #[serde(rename = "displayName")]
+        pub display_name: Option<String>,
+
+        #[serde(rename = "parentUuid", default)]
+        pub parent_uuid: Option<String>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_name_ignores_case() {
+        let mut catalog = Catalog::default();
+        catalog.insert("63E6C2B6-4A8E-869C-3D4C-E38355226584", "Odin");
+
+        assert_eq!(catalog.name("63e6c2b6-4a8e-869c-3d4c-e38355226584").unwrap(), "Odin");
+        assert!(catalog.name("unknown").is_none());
+    }
+
+    #[test]
+    fn test_qualified_name() {
+        let mut catalog = Catalog::default();
+        catalog.insert("episode", "EPISODE 5");
+        catalog.insert("act", "ACT III");
+        catalog.insert_parent("act", "episode");
+
+        assert_eq!(catalog.qualified_name("act").unwrap(), "EPISODE 5 // ACT III");
+        assert_eq!(catalog.qualified_name("episode").unwrap(), "EPISODE 5");
+    }
+}
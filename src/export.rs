@@ -0,0 +1,581 @@
+//! Flattens matches into normalized tables and writes them as CSV or Parquet.
+//!
+//! Every row carries `match_id`, so tables from many matches can be concatenated and joined.
+//! Columns are in the order of the row struct fields below and only ever get appended to.
+//! Player columns hold PUUIDs, times are in milliseconds and locations are world coordinates.
+//! Parquet files use the column types below; CSV leaves null values empty.
+
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use arrow_array::RecordBatch;
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use serde::Serialize;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::queue::QueueId;
+use crate::r#match::Match;
+
+/// Most matches exported into one archive. Archives are built in memory, so the export route
+/// rejects larger ranges and long histories have to be split into several requests.
+pub const MAX_EXPORT_MATCHES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "parquet" => Ok(ExportFormat::Parquet),
+            _ => Err(anyhow!("Unknown export format {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Utf8,
+    Boolean,
+    UInt32,
+    UInt64,
+    Float64,
+}
+
+impl From<ColumnType> for DataType {
+    fn from(column_type: ColumnType) -> Self {
+        match column_type {
+            ColumnType::Utf8 => DataType::Utf8,
+            ColumnType::Boolean => DataType::Boolean,
+            ColumnType::UInt32 => DataType::UInt32,
+            ColumnType::UInt64 => DataType::UInt64,
+            ColumnType::Float64 => DataType::Float64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+}
+
+impl Column {
+    pub const fn new(name: &'static str, column_type: ColumnType) -> Self {
+        Self {
+            name,
+            column_type,
+            nullable: false,
+        }
+    }
+
+    pub const fn nullable(name: &'static str, column_type: ColumnType) -> Self {
+        Self {
+            name,
+            column_type,
+            nullable: true,
+        }
+    }
+}
+
+/// A row of one of the tables, with its columns in field order.
+pub trait Row: Serialize {
+    const COLUMNS: &'static [Column];
+
+    fn schema() -> Schema {
+        Schema::new(
+            Self::COLUMNS
+                .iter()
+                .map(|c| Field::new(c.name, c.column_type.into(), c.nullable))
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+/// `matches.csv`, one row per match.
+#[derive(Debug, Serialize)]
+pub struct MatchRow {
+    pub match_id: String,
+    pub map_id: String,
+    pub queue_id: QueueId,
+    pub season_id: String,
+    pub is_ranked: bool,
+    /// Unix time in milliseconds
+    pub game_start: u64,
+    pub game_length: u64,
+    pub rounds: u32,
+    /// Empty for draws and free for all modes
+    pub winning_team: String,
+}
+
+impl Row for MatchRow {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("match_id", ColumnType::Utf8),
+        Column::new("map_id", ColumnType::Utf8),
+        Column::new("queue_id", ColumnType::Utf8),
+        Column::new("season_id", ColumnType::Utf8),
+        Column::new("is_ranked", ColumnType::Boolean),
+        Column::new("game_start", ColumnType::UInt64),
+        Column::new("game_length", ColumnType::UInt64),
+        Column::new("rounds", ColumnType::UInt32),
+        Column::new("winning_team", ColumnType::Utf8),
+    ];
+}
+
+/// `players.csv`, one row per player per match.
+#[derive(Debug, Serialize)]
+pub struct PlayerRow {
+    pub match_id: String,
+    pub player_id: String,
+    pub ign: String,
+    pub tagline: String,
+    pub team_id: String,
+    pub agent_id: String,
+    pub party_id: String,
+    pub rank: u32,
+    pub level: u32,
+    pub score: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub rounds_played: u32,
+    pub won: bool,
+}
+
+impl Row for PlayerRow {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("match_id", ColumnType::Utf8),
+        Column::new("player_id", ColumnType::Utf8),
+        Column::new("ign", ColumnType::Utf8),
+        Column::new("tagline", ColumnType::Utf8),
+        Column::new("team_id", ColumnType::Utf8),
+        Column::new("agent_id", ColumnType::Utf8),
+        Column::new("party_id", ColumnType::Utf8),
+        Column::new("rank", ColumnType::UInt32),
+        Column::new("level", ColumnType::UInt32),
+        Column::new("score", ColumnType::UInt32),
+        Column::new("kills", ColumnType::UInt32),
+        Column::new("deaths", ColumnType::UInt32),
+        Column::new("assists", ColumnType::UInt32),
+        Column::new("rounds_played", ColumnType::UInt32),
+        Column::new("won", ColumnType::Boolean),
+    ];
+}
+
+/// `kills.csv`, one row per kill.
+#[derive(Debug, Serialize)]
+pub struct KillRow {
+    pub match_id: String,
+    /// Round, times, victim location and damage are empty when Riot left them out
+    pub round: Option<u32>,
+    pub round_time: Option<u32>,
+    pub game_time: Option<u32>,
+    pub killer: String,
+    pub victim: String,
+    /// Assistant PUUIDs separated by `;`
+    pub assistants: String,
+    /// Empty when Riot did not record the killer's location
+    pub killer_x: Option<f64>,
+    pub killer_y: Option<f64>,
+    pub victim_x: Option<f64>,
+    pub victim_y: Option<f64>,
+    /// `Weapon`, `Ability`, `Bomb`, `Fall` or `Melee`
+    pub damage_type: Option<String>,
+    /// Weapon ID or ability slot
+    pub damage_item: Option<String>,
+    pub secondary_fire: Option<bool>,
+}
+
+impl Row for KillRow {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("match_id", ColumnType::Utf8),
+        Column::nullable("round", ColumnType::UInt32),
+        Column::nullable("round_time", ColumnType::UInt32),
+        Column::nullable("game_time", ColumnType::UInt32),
+        Column::new("killer", ColumnType::Utf8),
+        Column::new("victim", ColumnType::Utf8),
+        Column::new("assistants", ColumnType::Utf8),
+        Column::nullable("killer_x", ColumnType::Float64),
+        Column::nullable("killer_y", ColumnType::Float64),
+        Column::nullable("victim_x", ColumnType::Float64),
+        Column::nullable("victim_y", ColumnType::Float64),
+        Column::nullable("damage_type", ColumnType::Utf8),
+        Column::nullable("damage_item", ColumnType::Utf8),
+        Column::nullable("secondary_fire", ColumnType::Boolean),
+    ];
+}
+
+/// `rounds.csv`, one row per round.
+#[derive(Debug, Serialize)]
+pub struct RoundRow {
+    pub match_id: String,
+    pub round: u32,
+    pub winning_team: String,
+    /// `Elimination`, `Detonate`, `Defuse`, `Surrendered`, or empty when Riot didn't report it
+    pub result_code: String,
+    pub attacking_team: String,
+    pub planter: Option<String>,
+    pub plant_site: Option<String>,
+    pub plant_time: Option<u32>,
+    pub defuser: Option<String>,
+    pub defuse_time: Option<u32>,
+}
+
+impl Row for RoundRow {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("match_id", ColumnType::Utf8),
+        Column::new("round", ColumnType::UInt32),
+        Column::new("winning_team", ColumnType::Utf8),
+        Column::new("result_code", ColumnType::Utf8),
+        Column::new("attacking_team", ColumnType::Utf8),
+        Column::nullable("planter", ColumnType::Utf8),
+        Column::nullable("plant_site", ColumnType::Utf8),
+        Column::nullable("plant_time", ColumnType::UInt32),
+        Column::nullable("defuser", ColumnType::Utf8),
+        Column::nullable("defuse_time", ColumnType::UInt32),
+    ];
+}
+
+/// `damage.csv`, one row per attacker, receiver and round.
+#[derive(Debug, Serialize)]
+pub struct DamageRow {
+    pub match_id: String,
+    pub round: u32,
+    pub attacker: String,
+    pub receiver: String,
+    pub damage: u32,
+    pub headshots: u32,
+    pub bodyshots: u32,
+    pub legshots: u32,
+}
+
+impl Row for DamageRow {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("match_id", ColumnType::Utf8),
+        Column::new("round", ColumnType::UInt32),
+        Column::new("attacker", ColumnType::Utf8),
+        Column::new("receiver", ColumnType::Utf8),
+        Column::new("damage", ColumnType::UInt32),
+        Column::new("headshots", ColumnType::UInt32),
+        Column::new("bodyshots", ColumnType::UInt32),
+        Column::new("legshots", ColumnType::UInt32),
+    ];
+}
+
+#[derive(Debug, Default)]
+pub struct Tables {
+    pub matches: Vec<MatchRow>,
+    pub players: Vec<PlayerRow>,
+    pub kills: Vec<KillRow>,
+    pub rounds: Vec<RoundRow>,
+    pub damage: Vec<DamageRow>,
+}
+
+impl Tables {
+    pub fn from_matches<'a>(matches: impl IntoIterator<Item = &'a Match>) -> Self {
+        let mut tables = Tables::default();
+
+        for match_ in matches {
+            tables.add_match(match_);
+        }
+
+        tables
+    }
+
+    pub fn add_match(&mut self, match_: &Match) {
+        let match_id = &match_.match_info.match_id;
+        let winning_team = match_.teams.iter().find(|t| t.won).map(|t| t.team_id.clone());
+
+        self.matches.push(MatchRow {
+            match_id: match_id.clone(),
+            map_id: match_.match_info.map_id.clone(),
+            queue_id: match_.match_info.queue_id.clone(),
+            season_id: match_.match_info.season_id.clone(),
+            is_ranked: match_.match_info.is_ranked,
+            game_start: match_.match_info.game_start,
+            game_length: match_.match_info.game_length,
+            rounds: match_.round_results.len() as u32,
+            winning_team: winning_team.as_ref().map(|t| t.as_str().to_string()).unwrap_or_default(),
+        });
+
+        for player in match_.players.iter() {
+            self.players.push(PlayerRow {
+                match_id: match_id.clone(),
+                player_id: player.id.clone(),
+                ign: player.ign.clone(),
+                tagline: player.tagline.clone(),
+                team_id: player.team_id.as_str().to_string(),
+                agent_id: player.agent_id.clone(),
+                party_id: player.party_id.clone(),
+                rank: player.rank,
+                level: player.level,
+                score: player.stats.score,
+                kills: player.stats.kills,
+                deaths: player.stats.deaths,
+                assists: player.stats.assists,
+                rounds_played: player.stats.rounds_played,
+                won: winning_team.as_ref() == Some(&player.team_id),
+            });
+        }
+
+        for kill in match_.kills.iter() {
+            let damage = kill.finishing_damage.as_ref();
+            let (damage_type, damage_item) = damage.map(|d| d.source.parts()).unzip();
+            let killer_location = kill.killer_location();
+
+            self.kills.push(KillRow {
+                match_id: match_id.clone(),
+                round: kill.round,
+                round_time: kill.round_time,
+                game_time: kill.game_time,
+                killer: kill.killer.clone(),
+                victim: kill.victim.clone(),
+                assistants: kill.assistants.join(";"),
+                killer_x: killer_location.map(|l| l.x),
+                killer_y: killer_location.map(|l| l.y),
+                victim_x: kill.victim_location.map(|l| l.x),
+                victim_y: kill.victim_location.map(|l| l.y),
+                damage_type: damage_type.map(str::to_string),
+                damage_item: damage_item.map(str::to_string),
+                secondary_fire: damage.map(|d| d.secondary_fire),
+            });
+        }
+
+        for round in match_.round_results.iter() {
+            self.rounds.push(RoundRow {
+                match_id: match_id.clone(),
+                round: round.round_num,
+                winning_team: round.winning_team.as_str().to_string(),
+                result_code: round.result_code.as_str().to_string(),
+                attacking_team: match_.attacking_team(round.round_num).as_str().to_string(),
+                planter: round.plant.as_ref().map(|p| p.planter.clone()),
+                plant_site: round.plant.as_ref().map(|p| p.site.clone()),
+                plant_time: round.plant.as_ref().map(|p| p.round_time),
+                defuser: round.defuse.as_ref().map(|d| d.defuser.clone()),
+                defuse_time: round.defuse.as_ref().map(|d| d.round_time),
+            });
+
+            for stats in round.player_stats.iter() {
+                for damage in stats.damage.iter() {
+                    self.damage.push(DamageRow {
+                        match_id: match_id.clone(),
+                        round: round.round_num,
+                        attacker: stats.player_id.clone(),
+                        receiver: damage.receiver.clone(),
+                        damage: damage.damage,
+                        headshots: damage.headshots,
+                        bodyshots: damage.bodyshots,
+                        legshots: damage.legshots,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Each table as a file name and its contents.
+    pub fn to_files(&self, format: ExportFormat) -> Result<Vec<(String, Vec<u8>)>> {
+        fn write<T: Row>(rows: &[T], format: ExportFormat) -> Result<Vec<u8>> {
+            match format {
+                ExportFormat::Csv => write_csv(rows),
+                ExportFormat::Parquet => write_parquet(rows),
+            }
+        }
+
+        let file = |table: &str| format!("{}.{}", table, format.extension());
+
+        Ok(vec![
+            (file("matches"), write(&self.matches, format)?),
+            (file("players"), write(&self.players, format)?),
+            (file("kills"), write(&self.kills, format)?),
+            (file("rounds"), write(&self.rounds, format)?),
+            (file("damage"), write(&self.damage, format)?),
+        ])
+    }
+
+    /// Writes every table into `dir` as a file of its own.
+    pub fn write_dir(&self, dir: &Path, format: ExportFormat) -> Result<()> {
+        for (name, contents) in self.to_files(format)? {
+            File::create(dir.join(name))?.write_all(&contents)?;
+        }
+
+        Ok(())
+    }
+
+    /// A zip archive holding every table as a file of its own, built in memory.
+    pub fn to_zip(&self, format: ExportFormat) -> Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for (name, contents) in self.to_files(format)? {
+            zip.start_file(name, FileOptions::default())?;
+            zip.write_all(&contents)?;
+        }
+
+        Ok(zip.finish()?.into_inner())
+    }
+}
+
+/// Writes rows as CSV, always starting with the header.
+pub fn write_csv<T: Row>(rows: &[T]) -> Result<Vec<u8>> {
+    let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+
+    writer.write_record(T::COLUMNS.iter().map(|c| c.name))?;
+
+    for row in rows {
+        writer.serialize(row)?;
+    }
+
+    Ok(writer.into_inner()?)
+}
+
+/// Rows as a single Arrow record batch with the table's schema.
+pub fn to_record_batch<T: Row>(rows: &[T]) -> Result<RecordBatch> {
+    let schema = Arc::new(T::schema());
+    let mut decoder = arrow_json::ReaderBuilder::new(schema.clone())
+        .with_batch_size(rows.len().max(1))
+        .build_decoder()?;
+
+    decoder.serialize(rows)?;
+
+    Ok(decoder.flush()?.unwrap_or_else(|| RecordBatch::new_empty(schema)))
+}
+
+/// Writes rows as an uncompressed Parquet file.
+pub fn write_parquet<T: Row>(rows: &[T]) -> Result<Vec<u8>> {
+    let batch = to_record_batch(rows)?;
+    let mut writer = ArrowWriter::try_new(Vec::new(), batch.schema(), None)?;
+
+    writer.write(&batch)?;
+
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    const MATCH: &str = include_str!("../tests/fixtures/match.json");
+
+    #[test]
+    fn test_tables() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let tables = Tables::from_matches([&match_]);
+
+        assert_eq!(tables.matches.len(), 1);
+        assert_eq!(tables.matches[0].winning_team, "Blue");
+        assert_eq!(tables.players.len(), 4);
+        assert!(tables.players[2].won);
+        assert_eq!(tables.kills.len(), 8);
+        assert_eq!(tables.kills[1].assistants, "00000000-0000-0000-0000-000000000003");
+        assert_eq!(tables.kills[2].damage_type.as_deref(), Some("Ability"));
+        assert_eq!(tables.rounds[0].plant_site.as_deref(), Some("A"));
+        assert_eq!(tables.damage.len(), 16);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let tables = Tables::from_matches([&match_]);
+
+        let csv = String::from_utf8(write_csv(&tables.rounds).unwrap()).unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(
+            lines.next().unwrap(),
+            "match_id,round,winning_team,result_code,attacking_team,planter,plant_site,plant_time,defuser,defuse_time"
+        );
+        assert_eq!(lines.count(), 3);
+
+        let empty = String::from_utf8(write_csv::<DamageRow>(&[]).unwrap()).unwrap();
+        assert_eq!(empty, "match_id,round,attacker,receiver,damage,headshots,bodyshots,legshots\n");
+    }
+
+    fn serialized_header<T: Row>(rows: &[T]) -> String {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.serialize(&rows[0]).unwrap();
+
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        csv.lines().next().unwrap().to_string()
+    }
+
+    fn column_names<T: Row>() -> String {
+        T::COLUMNS.iter().map(|c| c.name).collect::<Vec<_>>().join(",")
+    }
+
+    #[test]
+    fn test_columns_match_fields() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let tables = Tables::from_matches([&match_]);
+
+        assert_eq!(serialized_header(&tables.matches), column_names::<MatchRow>());
+        assert_eq!(serialized_header(&tables.players), column_names::<PlayerRow>());
+        assert_eq!(serialized_header(&tables.kills), column_names::<KillRow>());
+        assert_eq!(serialized_header(&tables.rounds), column_names::<RoundRow>());
+        assert_eq!(serialized_header(&tables.damage), column_names::<DamageRow>());
+    }
+
+    #[test]
+    fn test_to_zip() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let zip = Tables::from_matches([&match_]).to_zip(ExportFormat::Csv).unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip)).unwrap();
+        assert_eq!(archive.len(), 5);
+
+        let mut players = String::new();
+        archive.by_name("players.csv").unwrap().read_to_string(&mut players).unwrap();
+        assert_eq!(players.lines().count(), 5);
+    }
+
+    #[test]
+    fn test_record_batch() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let tables = Tables::from_matches([&match_]);
+
+        let kills = to_record_batch(&tables.kills).unwrap();
+        assert_eq!(kills.num_rows(), 8);
+        assert_eq!(kills.schema().as_ref(), &KillRow::schema());
+
+        let rounds = to_record_batch(&tables.rounds).unwrap();
+        let plant_sites = rounds.column_by_name("plant_site").unwrap();
+        assert_eq!(plant_sites.null_count(), 3 - tables.rounds.iter().filter(|r| r.plant_site.is_some()).count());
+
+        let empty = to_record_batch::<DamageRow>(&[]).unwrap();
+        assert_eq!((empty.num_rows(), empty.num_columns()), (0, DamageRow::COLUMNS.len()));
+    }
+
+    #[test]
+    fn test_parquet_zip() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let zip = Tables::from_matches([&match_]).to_zip(ExportFormat::Parquet).unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip)).unwrap();
+        assert_eq!(archive.len(), 5);
+
+        let mut players = Vec::new();
+        archive.by_name("players.parquet").unwrap().read_to_end(&mut players).unwrap();
+        assert!(players.starts_with(b"PAR1") && players.ends_with(b"PAR1"));
+    }
+}
@@ -0,0 +1,282 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::projection::MapProjection;
+use crate::r#match::{DamageSource, Location, Match, PlayerId, Side, WeaponId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HeatmapKind {
+    /// Where the killer was standing
+    Kills,
+    /// Where the victim died
+    Deaths,
+}
+
+impl FromStr for HeatmapKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "kills" => Ok(HeatmapKind::Kills),
+            "deaths" => Ok(HeatmapKind::Deaths),
+            _ => Err(anyhow!("Unknown heatmap kind {}", s)),
+        }
+    }
+}
+
+/// Player and side filters apply to the killer for kill maps and to the victim for death maps.
+#[derive(Debug, Clone)]
+pub struct HeatmapFilter {
+    pub kind: HeatmapKind,
+    pub player: Option<PlayerId>,
+    pub side: Option<Side>,
+    pub round: Option<u32>,
+    pub weapon: Option<WeaponId>,
+}
+
+impl HeatmapFilter {
+    pub fn new(kind: HeatmapKind) -> Self {
+        Self {
+            kind,
+            player: None,
+            side: None,
+            round: None,
+            weapon: None,
+        }
+    }
+}
+
+/// World locations of the kills or deaths matching the filter. Kills missing a location, or the
+/// round or damage a filter asks about, are left out.
+pub fn locations(match_: &Match, filter: &HeatmapFilter) -> Vec<Location> {
+    match_
+        .kills
+        .iter()
+        .filter(|kill| filter.round.is_none_or(|round| kill.round == Some(round)))
+        .filter(|kill| match &filter.weapon {
+            Some(weapon) => kill
+                .finishing_damage
+                .as_ref()
+                .is_some_and(|damage| damage.source == DamageSource::Weapon(weapon.to_lowercase())),
+            None => true,
+        })
+        .filter_map(|kill| {
+            let (subject, location) = match filter.kind {
+                HeatmapKind::Kills => (&kill.killer, kill.killer_location()?),
+                HeatmapKind::Deaths => (&kill.victim, kill.victim_location?),
+            };
+
+            if filter.player.as_ref().is_some_and(|player| player != subject) {
+                return None;
+            }
+
+            if let Some(side) = filter.side {
+                let team = match_.team_of(subject)?;
+
+                if match_.side_of(team, kill.round?) != side {
+                    return None;
+                }
+            }
+
+            Some(location)
+        })
+        .collect()
+}
+
+/// Renders density on a coarse grid, blurred with a gaussian kernel and drawn over the minimap.
+#[derive(Debug, Clone)]
+pub struct HeatmapRenderer {
+    /// Width and height of the image in pixels
+    pub size: u32,
+    /// Number of grid cells along each axis
+    pub cells: u32,
+    /// Standard deviation of the blur, in cells
+    pub radius: f64,
+}
+
+impl Default for HeatmapRenderer {
+    fn default() -> Self {
+        Self {
+            size: 1024,
+            cells: 96,
+            radius: 1.5,
+        }
+    }
+}
+
+impl HeatmapRenderer {
+    /// Normalised density from 0 to 1, row major.
+    pub fn density(&self, projection: &MapProjection, locations: &[Location]) -> Vec<f64> {
+        let cells = self.cells as usize;
+        let mut grid = vec![0.0; cells * cells];
+        let reach = (self.radius * 3.0).ceil() as i64;
+
+        for location in locations {
+            let (x, y) = projection.project(*location);
+            let (cx, cy) = ((x * cells as f64) as i64, (y * cells as f64) as i64);
+
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let (gx, gy) = (cx + dx, cy + dy);
+
+                    if gx < 0 || gy < 0 || gx >= cells as i64 || gy >= cells as i64 {
+                        continue;
+                    }
+
+                    let distance = (dx * dx + dy * dy) as f64;
+                    grid[gy as usize * cells + gx as usize] += (-distance / (2.0 * self.radius * self.radius)).exp();
+                }
+            }
+        }
+
+        let max = grid.iter().cloned().fold(0.0, f64::max);
+        if max > 0.0 {
+            for value in grid.iter_mut() {
+                *value /= max;
+            }
+        }
+
+        grid
+    }
+
+    pub fn render_svg(&self, projection: &MapProjection, locations: &[Location]) -> String {
+        let size = self.size;
+        let cell_size = size as f64 / self.cells as f64;
+        let mut svg = String::new();
+
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+        );
+
+        if let Some(minimap) = projection.minimap_url.as_ref() {
+            let _ = writeln!(svg, r#"<image href="{minimap}" xlink:href="{minimap}" x="0" y="0" width="{size}" height="{size}"/>"#);
+        }
+
+        for (i, value) in self.density(projection, locations).into_iter().enumerate() {
+            if value < 0.02 {
+                continue;
+            }
+
+            let (gx, gy) = (i as u32 % self.cells, i as u32 / self.cells);
+            let (r, g, b) = heat_color(value);
+
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="rgb({r},{g},{b})" fill-opacity="{:.3}"/>"#,
+                gx as f64 * cell_size,
+                gy as f64 * cell_size,
+                cell_size,
+                cell_size,
+                0.2 + value * 0.6,
+            );
+        }
+
+        for location in locations {
+            let (x, y) = projection.to_pixels(*location, size);
+            let _ = writeln!(svg, r##"<circle cx="{x:.2}" cy="{y:.2}" r="3" fill="#ffffff" stroke="#000000" stroke-width="1"/>"##);
+        }
+
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+}
+
+/// Blue for sparse areas through to red for the densest.
+fn heat_color(value: f64) -> (u8, u8, u8) {
+    let value = value.clamp(0.0, 1.0);
+
+    let (r, g, b) = if value < 0.5 {
+        let t = value * 2.0;
+        (0.0, t, 1.0 - t)
+    } else {
+        let t = (value - 0.5) * 2.0;
+        (t, 1.0 - t, 0.0)
+    };
+
+    ((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH: &str = include_str!("../tests/fixtures/match.json");
+
+    fn ascent() -> MapProjection {
+        MapProjection {
+            map_id: "/Game/Maps/Ascent/Ascent".to_string(),
+            name: "Ascent".to_string(),
+            minimap_url: Some("https://media.valorant-api.com/maps/ascent.png".to_string()),
+            x_multiplier: 0.00007,
+            y_multiplier: -0.00007,
+            x_scalar: 0.813895,
+            y_scalar: 0.573242,
+        }
+    }
+
+    #[test]
+    fn test_filter_locations() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+
+        assert_eq!(locations(&match_, &HeatmapFilter::new(HeatmapKind::Deaths)).len(), 8);
+
+        let delta_kills = HeatmapFilter {
+            player: Some(match_.players[3].id.clone()),
+            ..HeatmapFilter::new(HeatmapKind::Kills)
+        };
+        assert_eq!(locations(&match_, &delta_kills).len(), 4);
+
+        let first_round = HeatmapFilter {
+            round: Some(0),
+            side: Some(Side::Attack),
+            ..HeatmapFilter::new(HeatmapKind::Kills)
+        };
+        assert_eq!(locations(&match_, &first_round).len(), 2);
+
+        let phantom = HeatmapFilter {
+            weapon: Some("EE8E8D15-496B-07AC-E5F6-8FAE5D4C7B1A".to_string()),
+            ..HeatmapFilter::new(HeatmapKind::Deaths)
+        };
+        assert_eq!(locations(&match_, &phantom).len(), 3);
+    }
+
+    #[test]
+    fn test_incomplete_kills_are_not_plotted() {
+        let mut value: serde_json::Value = serde_json::from_str(MATCH).unwrap();
+        let kills = value["kills"].as_array_mut().unwrap();
+        kills.push(serde_json::json!({
+            "killer": "00000000-0000-0000-0000-000000000001",
+            "victim": "00000000-0000-0000-0000-000000000003",
+        }));
+
+        let match_: Match = serde_json::from_value(value).unwrap();
+        assert_eq!(match_.kills.len(), 9);
+
+        let deaths = locations(&match_, &HeatmapFilter::new(HeatmapKind::Deaths));
+        assert_eq!(deaths.len(), 8);
+        assert!(!deaths.contains(&Location { x: 0.0, y: 0.0 }));
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let locations = locations(&match_, &HeatmapFilter::new(HeatmapKind::Deaths));
+
+        let renderer = HeatmapRenderer::default();
+        let density = renderer.density(&ascent(), &locations);
+
+        assert_eq!(density.len(), 96 * 96);
+        assert_eq!(density.iter().cloned().fold(0.0, f64::max), 1.0);
+
+        let svg = renderer.render_svg(&ascent(), &locations);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("ascent.png"));
+        assert_eq!(svg.matches("<circle").count(), 8);
+    }
+}
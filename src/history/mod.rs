@@ -1,11 +1,19 @@
+use std::collections::VecDeque;
+
 use anyhow::Result;
+use futures::{stream, Stream, TryStreamExt};
 use rocket::serde::Serialize;
 
 use crate::r#match::Match;
 use crate::auth::AuthData;
 use crate::common;
-use crate::requests::{Client, Method, Request};
+use crate::queue::QueueId;
+use crate::requests::{Client, Method, Pager, RateLimiter, Request};
+
+/// Riot returns at most this many history entries per request.
+pub const HISTORY_PAGE_SIZE: usize = 20;
 
+/// Coarse queue kind from before `queue_id` was added, still serialized for existing clients.
 #[derive(Debug, Serialize)]
 pub enum MatchType {
     Competitive,
@@ -14,61 +22,171 @@ pub enum MatchType {
     Other(String),
 }
 
+impl From<&QueueId> for MatchType {
+    fn from(queue: &QueueId) -> Self {
+        match queue {
+            QueueId::Competitive => MatchType::Competitive,
+            QueueId::Unrated => MatchType::Unrated,
+            QueueId::Deathmatch => MatchType::Deathmatch,
+            _ => MatchType::Other(queue.as_str().to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct MatchHistoryEntry {
     pub match_id: String,
+    /// Same as `queue_id`, in the original `/history` format
     pub match_type: MatchType,
+    pub queue_id: QueueId,
     pub start_time: u64,
     pub game_data: Option<Match>,
 }
 
 impl From<&response::MatchHistoryEntry> for MatchHistoryEntry {
     fn from(match_: &response::MatchHistoryEntry) -> Self {
-        let match_type = match match_.match_type.as_str() {
-            "competitive" => MatchType::Competitive,
-            "unrated" => MatchType::Unrated,
-            "deathmatch" => MatchType::Deathmatch,
-            _ => MatchType::Other(match_.match_type.clone()),
-        };
-
         Self {
             match_id: match_.match_id.clone(),
-            match_type,
-            start_time: match_.start_time.clone(),
+            match_type: (&match_.queue_id).into(),
+            queue_id: match_.queue_id.clone(),
+            start_time: match_.start_time,
             game_data: None,
         }
     }
 }
 
-pub async fn get_match_history(client: &Client, auth_data: &AuthData, player_id: &str) -> Result<Vec<MatchHistoryEntry>> {
-    let url = format!(
-        "https://pd.{}.a.pvp.net/match-history/v1/history/{}?queue=custom",
-        "na", player_id /*auth_data.user_id*/
+/// Fetches one page of history entries without match details, newest first. `end` is exclusive.
+pub async fn get_match_history_page(client: &Client, auth_data: &AuthData, player_id: &str, queue: Option<&QueueId>, start: usize, end: usize) -> Result<Vec<MatchHistoryEntry>> {
+    let history = get_history(client, auth_data, player_id, queue, start, end).await?;
+
+    Ok(history.matches.iter().map(|m| m.into()).collect())
+}
+
+async fn get_history(client: &Client, auth_data: &AuthData, player_id: &str, queue: Option<&QueueId>, start: usize, end: usize) -> Result<response::History> {
+    let mut url = format!(
+        "https://pd.{}.a.pvp.net/match-history/v1/history/{}?startIndex={}&endIndex={}",
+        "na", player_id /*auth_data.user_id*/, start, end
     );
 
+    if let Some(queue) = queue {
+        url.push_str(&format!("&queue={}", queue.as_str()));
+    }
+
     let mut req = Request::new(client, url, Method::GET);
     req.append_headers(auth_data.get_auth_headers());
     req.append_headers(common::get_client_headers().await?);
 
     let res = req.send().await?;
 
-    let history: response::History = res.json().await?;
-    let mut history: Vec<MatchHistoryEntry> = history.matches.iter().map(|m| m.into()).collect();
+    Ok(res.json().await?)
+}
+
+/// Matches of a player from `start` up to `end` with their details, from every queue unless one is given.
+/// Without an `end`, pages through the history until Riot runs out.
+pub async fn get_match_history(client: &Client, auth_data: &AuthData, player_id: &str, queue: Option<&QueueId>, start: usize, end: Option<usize>) -> Result<Vec<MatchHistoryEntry>> {
+    let query = HistoryQuery {
+        queue: queue.cloned(),
+        start,
+        end,
+    };
+
+    stream_match_history(client, auth_data, player_id, query, true, None).try_collect().await
+}
+
+/// Which part of a player's history to fetch. Without an `end`, everything from `start` on.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    pub queue: Option<QueueId>,
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+struct HistoryCursor<'a> {
+    client: &'a Client,
+    auth_data: &'a AuthData,
+    player_id: &'a str,
+    query: HistoryQuery,
+    with_details: bool,
+    limiter: Option<&'a RateLimiter>,
+    page: VecDeque<MatchHistoryEntry>,
+    pager: Pager,
+}
+
+impl HistoryCursor<'_> {
+    async fn next_entry(&mut self) -> Option<Result<MatchHistoryEntry>> {
+        if self.page.is_empty() {
+            if let Some((start, end)) = self.pager.next_page() {
+                self.wait().await;
+
+                match get_history(self.client, self.auth_data, self.player_id, self.query.queue.as_ref(), start, end).await {
+                    Ok(history) => {
+                        self.pager.advance(history.matches.len(), history.total);
+                        self.page.extend(history.matches.iter().map(MatchHistoryEntry::from));
+                    }
+                    Err(err) => {
+                        self.pager.stop();
+                        return Some(Err(err));
+                    }
+                }
+            }
+        }
+
+        let mut entry = self.page.pop_front()?;
+
+        // One match Riot sends in an unexpected shape shouldn't hide the rest of the history.
+        if self.with_details {
+            self.wait().await;
 
-    for match_ in history.iter_mut() {
-        match_.game_data = Some(Match::from_id(client, auth_data, &match_.match_id).await?);
+            match Match::from_id(self.client, self.auth_data, &entry.match_id).await {
+                Ok(game_data) => entry.game_data = Some(game_data),
+                Err(err) => log::warn!("skipping details of match {}: {}", entry.match_id, err),
+            }
+        }
+
+        Some(Ok(entry))
+    }
+
+    async fn wait(&self) {
+        if let Some(limiter) = self.limiter {
+            limiter.wait().await;
+        }
     }
+}
 
-    Ok(history)
+/// Yields history entries as pages come in, loading each match's details first if asked to.
+/// Pages are only requested once the previous one has been consumed, and dropping the stream
+/// cancels whatever request is in flight. An error ends the stream.
+pub fn stream_match_history<'a>(client: &'a Client, auth_data: &'a AuthData, player_id: &'a str, query: HistoryQuery, with_details: bool, limiter: Option<&'a RateLimiter>) -> impl Stream<Item = Result<MatchHistoryEntry>> + 'a {
+    let cursor = HistoryCursor {
+        client,
+        auth_data,
+        player_id,
+        pager: Pager::new(query.start, query.end, HISTORY_PAGE_SIZE),
+        query,
+        with_details,
+        limiter,
+        page: VecDeque::new(),
+    };
+
+    stream::unfold(cursor, |mut cursor| async move {
+        let entry = cursor.next_entry().await?;
+        Some((entry, cursor))
+    })
 }
 
 mod response {
     use serde::Deserialize;
 
+    use crate::queue::QueueId;
+
     #[derive(Debug, Deserialize)]
     pub struct History {
         #[serde(rename = "History")]
         pub matches: Vec<MatchHistoryEntry>,
+
+        /// Entries in the player's whole history for the queue asked for
+        #[serde(rename = "Total", default)]
+        pub total: Option<usize>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -77,7 +195,7 @@ mod response {
         pub match_id: String,
 
         #[serde(rename = "QueueID")]
-        pub match_type: String,
+        pub queue_id: QueueId,
 
         #[serde(rename = "GameStartTime")]
         pub start_time: u64,
@@ -4,6 +4,7 @@ mod shop;
 pub use wallet::*;
 pub use shop::*;
 
+#[allow(dead_code)]
 #[derive(Debug)]
 struct Weapon {
     id: String,
@@ -24,7 +24,7 @@ pub async fn get_shop(client: &Client, auth_data: &AuthData) -> Result<Vec<Offer
 
     let offers = json.get("SkinsPanelLayout").unwrap().get("SingleItemOffers").unwrap();
 
-    return match offers.as_array() {
+    match offers.as_array() {
         Some(offers) => {
             Ok(offers
                 .iter()
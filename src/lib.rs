@@ -0,0 +1,27 @@
+pub mod r#match;
+pub mod auth;
+pub mod requests;
+pub mod tls;
+pub mod common;
+pub mod inventory;
+pub mod history;
+pub mod player;
+pub mod account_xp;
+pub mod content;
+pub mod loadout;
+pub mod mmr;
+pub mod analytics;
+pub mod projection;
+pub mod heatmap;
+pub mod timeline;
+pub mod export;
+pub mod queue;
+pub mod store;
+
+#[macro_export] macro_rules! hashmap {
+    ($( $key: expr => $val: expr ),*) => {{
+        let mut map = ::std::collections::HashMap::new();
+        $( map.insert($key, $val); )*
+        map
+    }}
+}
@@ -0,0 +1,407 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::auth::AuthData;
+use crate::common;
+use crate::content::Catalog;
+use crate::requests::*;
+
+/// Names are only filled in by [`Loadout::resolve_names`] and are ignored when saving.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gun {
+    pub weapon_id: String,
+    pub skin_id: String,
+    pub skin_level_id: String,
+    pub chroma_id: String,
+    pub buddy: Option<Buddy>,
+    #[serde(default)]
+    pub attachments: Vec<Value>,
+
+    #[serde(default)]
+    pub weapon_name: Option<String>,
+    #[serde(default)]
+    pub skin_name: Option<String>,
+    #[serde(default)]
+    pub skin_level_name: Option<String>,
+    #[serde(default)]
+    pub chroma_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Buddy {
+    pub instance_id: String,
+    pub buddy_id: String,
+    pub buddy_level_id: String,
+
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spray {
+    pub equip_slot_id: String,
+    pub spray_id: String,
+    pub spray_level_id: Option<String>,
+
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Identity {
+    pub player_card_id: String,
+    pub player_title_id: String,
+    pub level_border_id: String,
+    pub account_level: u32,
+    pub hide_account_level: bool,
+
+    #[serde(default)]
+    pub player_card_name: Option<String>,
+    #[serde(default)]
+    pub player_title_name: Option<String>,
+    #[serde(default)]
+    pub level_border_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Loadout {
+    pub subject: String,
+    pub version: u32,
+    pub guns: Vec<Gun>,
+    pub sprays: Vec<Spray>,
+    pub identity: Identity,
+    pub incognito: bool,
+}
+
+impl Loadout {
+    pub fn resolve_names(&mut self, catalog: &Catalog) {
+        for gun in self.guns.iter_mut() {
+            gun.weapon_name = catalog.name(&gun.weapon_id);
+            gun.skin_name = catalog.name(&gun.skin_id);
+            gun.skin_level_name = catalog.name(&gun.skin_level_id);
+            gun.chroma_name = catalog.name(&gun.chroma_id);
+
+            if let Some(buddy) = gun.buddy.as_mut() {
+                buddy.name = catalog.name(&buddy.buddy_level_id);
+            }
+        }
+
+        for spray in self.sprays.iter_mut() {
+            spray.name = catalog.name(&spray.spray_id);
+        }
+
+        self.identity.player_card_name = catalog.name(&self.identity.player_card_id);
+        self.identity.player_title_name = catalog.name(&self.identity.player_title_id);
+        self.identity.level_border_name = catalog.name(&self.identity.level_border_id);
+    }
+}
+
+impl From<response::Gun> for Gun {
+    fn from(gun: response::Gun) -> Self {
+        let buddy = match (gun.charm_instance_id, gun.charm_id, gun.charm_level_id) {
+            (Some(instance_id), Some(buddy_id), Some(buddy_level_id)) => Some(Buddy {
+                instance_id,
+                buddy_id,
+                buddy_level_id,
+                name: None,
+            }),
+            _ => None,
+        };
+
+        Self {
+            weapon_id: gun.id,
+            skin_id: gun.skin_id,
+            skin_level_id: gun.skin_level_id,
+            chroma_id: gun.chroma_id,
+            buddy,
+            attachments: gun.attachments,
+            weapon_name: None,
+            skin_name: None,
+            skin_level_name: None,
+            chroma_name: None,
+        }
+    }
+}
+
+impl From<Gun> for response::Gun {
+    fn from(gun: Gun) -> Self {
+        let (charm_instance_id, charm_id, charm_level_id) = match gun.buddy {
+            Some(buddy) => (Some(buddy.instance_id), Some(buddy.buddy_id), Some(buddy.buddy_level_id)),
+            None => (None, None, None),
+        };
+
+        Self {
+            id: gun.weapon_id,
+            skin_id: gun.skin_id,
+            skin_level_id: gun.skin_level_id,
+            chroma_id: gun.chroma_id,
+            charm_instance_id,
+            charm_id,
+            charm_level_id,
+            attachments: gun.attachments,
+        }
+    }
+}
+
+impl From<response::Spray> for Spray {
+    fn from(spray: response::Spray) -> Self {
+        Self {
+            equip_slot_id: spray.equip_slot_id,
+            spray_id: spray.spray_id,
+            spray_level_id: spray.spray_level_id,
+            name: None,
+        }
+    }
+}
+
+impl From<Spray> for response::Spray {
+    fn from(spray: Spray) -> Self {
+        Self {
+            equip_slot_id: spray.equip_slot_id,
+            spray_id: spray.spray_id,
+            spray_level_id: spray.spray_level_id,
+        }
+    }
+}
+
+impl From<response::Identity> for Identity {
+    fn from(identity: response::Identity) -> Self {
+        Self {
+            player_card_id: identity.player_card_id,
+            player_title_id: identity.player_title_id,
+            level_border_id: identity.preferred_level_border_id,
+            account_level: identity.account_level,
+            hide_account_level: identity.hide_account_level,
+            player_card_name: None,
+            player_title_name: None,
+            level_border_name: None,
+        }
+    }
+}
+
+impl From<Identity> for response::Identity {
+    fn from(identity: Identity) -> Self {
+        Self {
+            player_card_id: identity.player_card_id,
+            player_title_id: identity.player_title_id,
+            preferred_level_border_id: identity.level_border_id,
+            account_level: identity.account_level,
+            hide_account_level: identity.hide_account_level,
+        }
+    }
+}
+
+impl From<response::Loadout> for Loadout {
+    fn from(loadout: response::Loadout) -> Self {
+        Self {
+            subject: loadout.subject,
+            version: loadout.version,
+            guns: loadout.guns.into_iter().map(|g| g.into()).collect(),
+            sprays: loadout.sprays.into_iter().map(|s| s.into()).collect(),
+            identity: loadout.identity.into(),
+            incognito: loadout.incognito,
+        }
+    }
+}
+
+impl From<Loadout> for response::Loadout {
+    fn from(loadout: Loadout) -> Self {
+        Self {
+            subject: loadout.subject,
+            version: loadout.version,
+            guns: loadout.guns.into_iter().map(|g| g.into()).collect(),
+            sprays: loadout.sprays.into_iter().map(|s| s.into()).collect(),
+            identity: loadout.identity.into(),
+            incognito: loadout.incognito,
+        }
+    }
+}
+
+fn loadout_url(auth_data: &AuthData) -> String {
+    format!(
+        "https://pd.{}.a.pvp.net/personalization/v2/players/{}/playerloadout",
+        "na", auth_data.user_id
+    )
+}
+
+pub async fn get_loadout(client: &Client, auth_data: &AuthData) -> Result<Loadout> {
+    let mut req = Request::new(client, loadout_url(auth_data), Method::GET);
+    req.append_headers(auth_data.get_auth_headers());
+    req.append_headers(common::get_client_headers().await?);
+
+    let res = req.send().await?;
+
+    let data: response::Loadout = res.json().await?;
+
+    Ok(data.into())
+}
+
+/// Saves the loadout and returns it as Riot stored it.
+pub async fn set_loadout(client: &Client, auth_data: &AuthData, loadout: Loadout) -> Result<Loadout> {
+    let body: response::Loadout = loadout.into();
+
+    let mut req = Request::new(client, loadout_url(auth_data), Method::PUT);
+    req.append_headers(auth_data.get_auth_headers());
+    req.append_headers(common::get_client_headers().await?);
+    req.set_json_body(&body)?;
+
+    let res = req.send().await?;
+
+    let data: response::Loadout = res.error_for_status()?.json().await?;
+
+    Ok(data.into())
+}
+
+mod response {
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Loadout {
+        #[serde(rename = "Subject")]
+        pub subject: String,
+
+        #[serde(rename = "Version")]
+        pub version: u32,
+
+        #[serde(rename = "Guns")]
+        pub guns: Vec<Gun>,
+
+        #[serde(rename = "Sprays")]
+        pub sprays: Vec<Spray>,
+
+        #[serde(rename = "Identity")]
+        pub identity: Identity,
+
+        #[serde(rename = "Incognito")]
+        pub incognito: bool,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Gun {
+        #[serde(rename = "ID")]
+        pub id: String,
+
+        #[serde(rename = "SkinID")]
+        pub skin_id: String,
+
+        #[serde(rename = "SkinLevelID")]
+        pub skin_level_id: String,
+
+        #[serde(rename = "ChromaID")]
+        pub chroma_id: String,
+
+        #[serde(rename = "CharmInstanceID", skip_serializing_if = "Option::is_none")]
+        pub charm_instance_id: Option<String>,
+
+        #[serde(rename = "CharmID", skip_serializing_if = "Option::is_none")]
+        pub charm_id: Option<String>,
+
+        #[serde(rename = "CharmLevelID", skip_serializing_if = "Option::is_none")]
+        pub charm_level_id: Option<String>,
+
+        #[serde(rename = "Attachments", default)]
+        pub attachments: Vec<Value>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Spray {
+        #[serde(rename = "EquipSlotID")]
+        pub equip_slot_id: String,
+
+        #[serde(rename = "SprayID")]
+        pub spray_id: String,
+
+        #[serde(rename = "SprayLevelID")]
+        pub spray_level_id: Option<String>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Identity {
+        #[serde(rename = "PlayerCardID")]
+        pub player_card_id: String,
+
+        #[serde(rename = "PlayerTitleID")]
+        pub player_title_id: String,
+
+        #[serde(rename = "AccountLevel")]
+        pub account_level: u32,
+
+        #[serde(rename = "PreferredLevelBorderID")]
+        pub preferred_level_border_id: String,
+
+        #[serde(rename = "HideAccountLevel")]
+        pub hide_account_level: bool,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOADOUT: &str = r#"{
+        "Subject": "player",
+        "Version": 12,
+        "Guns": [
+            {
+                "ID": "63e6c2b6-4a8e-869c-3d4c-e38355226584",
+                "SkinID": "skin",
+                "SkinLevelID": "skin-level",
+                "ChromaID": "chroma",
+                "CharmInstanceID": "instance",
+                "CharmID": "charm",
+                "CharmLevelID": "charm-level",
+                "Attachments": []
+            },
+            {
+                "ID": "29a0cfab-485b-f5d5-779a-b59f85e204a8",
+                "SkinID": "classic",
+                "SkinLevelID": "classic-level",
+                "ChromaID": "classic-chroma",
+                "Attachments": []
+            }
+        ],
+        "Sprays": [{"EquipSlotID": "slot", "SprayID": "spray", "SprayLevelID": null}],
+        "Identity": {
+            "PlayerCardID": "card",
+            "PlayerTitleID": "title",
+            "AccountLevel": 100,
+            "PreferredLevelBorderID": "border",
+            "HideAccountLevel": false
+        },
+        "Incognito": false
+    }"#;
+
+    #[test]
+    fn test_loadout_round_trip() {
+        let data: response::Loadout = serde_json::from_str(LOADOUT).unwrap();
+        let loadout: Loadout = data.into();
+
+        assert_eq!(loadout.guns[0].buddy.as_ref().unwrap().buddy_id, "charm");
+        assert!(loadout.guns[1].buddy.is_none());
+
+        let body: response::Loadout = loadout.into();
+
+        assert_eq!(
+            serde_json::to_value(body).unwrap(),
+            serde_json::from_str::<Value>(LOADOUT).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_resolve_names() {
+        let data: response::Loadout = serde_json::from_str(LOADOUT).unwrap();
+        let mut loadout: Loadout = data.into();
+
+        let mut catalog = Catalog::default();
+        catalog.insert("63e6c2b6-4a8e-869c-3d4c-e38355226584", "Odin");
+        catalog.insert("card", "Build Your Own Vandal Card");
+
+        loadout.resolve_names(&catalog);
+
+        assert_eq!(loadout.guns[0].weapon_name.as_deref(), Some("Odin"));
+        assert_eq!(loadout.identity.player_card_name.as_deref(), Some("Build Your Own Vandal Card"));
+        assert!(loadout.guns[1].weapon_name.is_none());
+    }
+}
@@ -1,32 +1,50 @@
 #[macro_use]
 extern crate rocket;
 
-use rocket::http::{Cookie, Status};
-use rocket::response::{content, status};
+use std::collections::HashSet;
+
+use rocket::http::{self, Cookie, Status};
 use rocket::serde::{Deserialize, Serialize};
 use rocket::serde::json::Json;
 use rocket::http::CookieJar;
+use rocket::futures::StreamExt;
+use rocket::response::stream::TextStream;
+use rocket::{Shutdown, State};
 
-use requests::*;
-
-use crate::auth::AuthData;
-use crate::history::MatchHistoryEntry;
-
-mod r#match;
-mod auth;
-mod requests;
-mod tls;
-mod common;
-mod inventory;
-mod history;
-mod player;
+use valorant::requests::*;
+use valorant::account_xp::{self, AccountXp};
+use valorant::content::{Catalog, ContentType};
+use valorant::loadout::{self, Loadout};
+use valorant::mmr::{self, CompetitiveUpdate, SeasonSummary};
+use valorant::analytics::{self, AggregateStats, DuelReport, EconomyReport, EconomyThresholds, PlayerMetrics, SessionReport, SideReport, StatsFilter};
+use valorant::r#match::Match;
+use valorant::heatmap::{self, HeatmapFilter, HeatmapRenderer};
+use valorant::projection::{MapProjection, ProjectionError};
+use valorant::timeline::{self, TimelineEntry};
+use valorant::export::{self, ExportFormat, Tables};
+use valorant::auth::{self, AuthData};
+use valorant::history::{self, MatchHistoryEntry};
+use valorant::queue::QueueId;
+use valorant::store::{self, PlayerMatchStats, Store, SyncReport};
+use valorant::player::{self, PlayerCache, PlayerLookup};
 
 #[rocket::main]
 async fn main() {
     let _ = rocket::build()
         // .mount("/", routes![index])
         .mount("/auth", routes![login])
-        .mount("/history", routes![get_match_history])
+        .mount("/history", routes![get_match_history, stream_match_history])
+        .mount("/account-xp", routes![get_account_xp])
+        .mount("/loadout", routes![get_loadout, set_loadout])
+        .mount("/mmr", routes![get_competitive_updates, get_season_summaries])
+        .mount("/players", routes![get_players])
+        .mount("/export", routes![export_history])
+        .mount("/store", routes![sync_player, get_stored_history, get_stored_stats])
+        .mount("/stats", routes![get_aggregate_stats, get_sessions])
+        .mount("/matches", routes![get_match, get_scoreboard, get_economy, get_heatmap, get_sides, get_timeline, get_timeline_ndjson, get_duels])
+        .manage(PlayerCache::default())
+        .manage(RateLimiter::default())
+        .manage(Store::open(std::env::var("VALORANT_DB").unwrap_or_else(|_| "valorant.db".to_string())).unwrap())
         .launch().await;
 }
 
@@ -66,27 +84,415 @@ async fn login(cookie_jar: &CookieJar<'_>, auth_request: Json<AuthRequest<'_>>)
     Json(auth_data)
 }
 
+/// `player_id` is either a PUUID or a `GameName#Tag` Riot ID.
 #[derive(Serialize, Deserialize)]
 struct MatchHistoryRequest<'a> {
     player_id: &'a str,
 }
 
-#[get("/", data = "<req_data>")]
-async fn get_match_history(cookie_jar: &CookieJar<'_>, req_data: Json<MatchHistoryRequest<'_>>) -> Json<Vec<MatchHistoryEntry>> {
+/// `queue` limits the history to one queue. `start` and `end` index into the history and default
+/// to the first page, `all` fetches everything from `start` on.
+#[get("/?<queue>&<start>&<end>&<all>", data = "<req_data>")]
+async fn get_match_history(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, req_data: Json<MatchHistoryRequest<'_>>, queue: Option<&str>, start: Option<usize>, end: Option<usize>, all: Option<bool>) -> Json<Vec<MatchHistoryEntry>> {
     let client = Client::new().unwrap();
 
-    println!("{:?}", req_data.player_id);
     let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
 
-    let history = history::get_match_history(&client, &auth_data, req_data.player_id).await.unwrap();
+    let player_id = player::resolve_player_id(&client, &auth_data, cache, req_data.player_id).await.unwrap();
+
+    let queue = queue.map(QueueId::from);
+    let start = start.unwrap_or(0);
+    let end = if all.unwrap_or(false) { None } else { Some(end.unwrap_or(start + history::HISTORY_PAGE_SIZE)) };
+
+    let mut history = history::get_match_history(&client, &auth_data, &player_id, queue.as_ref(), start, end).await.unwrap();
+
+    let mut matches: Vec<&mut Match> = history.iter_mut().filter_map(|entry| entry.game_data.as_mut()).collect();
+    cache.fill_names(&client, &auth_data, &mut matches).await.unwrap();
 
     Json(history)
 }
 
-#[macro_export] macro_rules! hashmap {
-    ($( $key: expr => $val: expr ),*) => {{
-        let mut map = ::std::collections::HashMap::new();
-        $( map.insert($key, $val); )*
-        map
-    }}
+/// Same parameters as `/history`, except that without an `end` the whole history is streamed.
+/// Entries are written as newline delimited JSON as they are fetched, with match details
+/// unless `details` is false. The stream ends early if the server shuts down.
+#[get("/stream?<queue>&<start>&<end>&<details>", data = "<req_data>")]
+#[allow(clippy::too_many_arguments)]
+async fn stream_match_history<'r>(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, limiter: &'r State<RateLimiter>, mut shutdown: Shutdown, req_data: Json<MatchHistoryRequest<'_>>, queue: Option<&str>, start: Option<usize>, end: Option<usize>, details: Option<bool>) -> (http::ContentType, TextStream![String + 'r]) {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let player_id = player::resolve_player_id(&client, &auth_data, cache, req_data.player_id).await.unwrap();
+
+    let query = history::HistoryQuery {
+        queue: queue.map(QueueId::from),
+        start: start.unwrap_or(0),
+        end,
+    };
+
+    let stream = TextStream! {
+        let mut history = std::pin::pin!(history::stream_match_history(&client, &auth_data, &player_id, query, details.unwrap_or(true), Some(limiter)));
+
+        loop {
+            let entry = rocket::tokio::select! {
+                entry = history.next() => entry,
+                _ = &mut shutdown => break,
+            };
+
+            match entry {
+                Some(Ok(entry)) => yield serde_json::to_string(&entry).unwrap() + "\n",
+                Some(Err(err)) => {
+                    log::warn!("match history stream for {} failed: {}", player_id, err);
+                    break;
+                }
+                None => break,
+            }
+        }
+    };
+
+    (http::ContentType::new("application", "x-ndjson"), stream)
+}
+
+#[derive(Serialize, Deserialize)]
+struct PlayersRequest {
+    player_ids: Vec<String>,
+}
+
+#[get("/", data = "<req_data>")]
+async fn get_players(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, req_data: Json<PlayersRequest>) -> Json<PlayerLookup> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let players = cache.resolve(&client, &auth_data, &req_data.player_ids).await.unwrap();
+
+    Json(players)
+}
+
+/// Match details with every player's Riot ID filled in.
+#[get("/<match_id>")]
+async fn get_match(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, match_id: &str) -> Json<Match> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let mut match_ = Match::from_id(&client, &auth_data, match_id).await.unwrap();
+    cache.fill_names(&client, &auth_data, &mut [&mut match_]).await.unwrap();
+
+    Json(match_)
+}
+
+#[get("/")]
+async fn get_account_xp(cookie_jar: &CookieJar<'_>) -> Json<AccountXp> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let account_xp = account_xp::get_account_xp(&client, &auth_data).await.unwrap();
+
+    Json(account_xp)
+}
+
+const LOADOUT_CONTENT: &[ContentType] = &[
+    ContentType::Weapons,
+    ContentType::Skins,
+    ContentType::SkinLevels,
+    ContentType::SkinChromas,
+    ContentType::BuddyLevels,
+    ContentType::Sprays,
+    ContentType::PlayerCards,
+    ContentType::PlayerTitles,
+    ContentType::LevelBorders,
+];
+
+#[get("/")]
+async fn get_loadout(cookie_jar: &CookieJar<'_>) -> Json<Loadout> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let mut loadout = loadout::get_loadout(&client, &auth_data).await.unwrap();
+
+    // Names are a nice to have, the loadout is still usable without the catalog
+    if let Ok(catalog) = Catalog::fetch(&client, LOADOUT_CONTENT).await {
+        loadout.resolve_names(&catalog);
+    }
+
+    Json(loadout)
+}
+
+#[put("/", data = "<loadout>")]
+async fn set_loadout(cookie_jar: &CookieJar<'_>, loadout: Json<Loadout>) -> Json<Loadout> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let loadout = loadout::set_loadout(&client, &auth_data, loadout.into_inner()).await.unwrap();
+
+    Json(loadout)
+}
+
+/// `player_id` is either a PUUID or a `GameName#Tag` Riot ID.
+#[derive(Serialize, Deserialize)]
+struct CompetitiveUpdatesRequest<'a> {
+    player_id: &'a str,
+    limit: Option<usize>,
+}
+
+#[get("/updates", data = "<req_data>")]
+async fn get_competitive_updates(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, req_data: Json<CompetitiveUpdatesRequest<'_>>) -> Json<Vec<CompetitiveUpdate>> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let player_id = player::resolve_player_id(&client, &auth_data, cache, req_data.player_id).await.unwrap();
+
+    let updates = mmr::get_all_competitive_updates(&client, &auth_data, &player_id, req_data.limit).await.unwrap();
+
+    Json(updates)
+}
+
+/// `player_id` is either a PUUID or a `GameName#Tag` Riot ID.
+#[derive(Serialize, Deserialize)]
+struct SeasonSummariesRequest<'a> {
+    player_id: &'a str,
+}
+
+#[get("/seasons", data = "<req_data>")]
+async fn get_season_summaries(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, req_data: Json<SeasonSummariesRequest<'_>>) -> Json<Vec<SeasonSummary>> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let player_id = player::resolve_player_id(&client, &auth_data, cache, req_data.player_id).await.unwrap();
+
+    let mut seasons = mmr::get_season_summaries(&client, &auth_data, &player_id).await.unwrap();
+
+    if let Ok(catalog) = Catalog::fetch(&client, &[ContentType::Seasons]).await {
+        for season in seasons.iter_mut() {
+            season.resolve_name(&catalog);
+        }
+    }
+
+    Json(seasons)
+}
+
+#[get("/<match_id>/scoreboard")]
+async fn get_scoreboard(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, match_id: &str) -> Json<Vec<PlayerMetrics>> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let mut match_ = Match::from_id(&client, &auth_data, match_id).await.unwrap();
+    cache.fill_names(&client, &auth_data, &mut [&mut match_]).await.unwrap();
+
+    Json(analytics::scoreboard(&match_))
+}
+
+#[get("/<match_id>/economy?<eco_max>&<full_buy_min>&<force_remaining_max>")]
+async fn get_economy(cookie_jar: &CookieJar<'_>, match_id: &str, eco_max: Option<u32>, full_buy_min: Option<u32>, force_remaining_max: Option<u32>) -> Json<EconomyReport> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let match_ = Match::from_id(&client, &auth_data, match_id).await.unwrap();
+
+    let defaults = EconomyThresholds::for_mode(match_.mode());
+    let thresholds = EconomyThresholds {
+        eco_max: eco_max.unwrap_or(defaults.eco_max),
+        full_buy_min: full_buy_min.unwrap_or(defaults.full_buy_min),
+        force_remaining_max: force_remaining_max.unwrap_or(defaults.force_remaining_max),
+        ..defaults
+    };
+
+    Json(analytics::economy(&match_, &thresholds))
+}
+
+#[get("/<match_id>/heatmap.svg?<kind>&<player>&<side>&<round>&<weapon>")]
+async fn get_heatmap(cookie_jar: &CookieJar<'_>, match_id: &str, kind: Option<&str>, player: Option<String>, side: Option<&str>, round: Option<u32>, weapon: Option<String>) -> Result<(http::ContentType, String), Status> {
+    let kind = kind.unwrap_or("kills").parse().map_err(|_| Status::BadRequest)?;
+    let side = side.map(str::parse).transpose().map_err(|_| Status::BadRequest)?;
+
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let match_ = Match::from_id(&client, &auth_data, match_id).await.unwrap();
+    let projection = MapProjection::fetch(&client, &match_.match_info.map_id).await.map_err(|e| {
+        log::warn!("no projection for {}: {:#}", match_.match_info.map_id, e);
+
+        if e.is::<ProjectionError>() { Status::NotFound } else { Status::BadGateway }
+    })?;
+
+    let filter = HeatmapFilter {
+        player,
+        side,
+        round,
+        weapon,
+        ..HeatmapFilter::new(kind)
+    };
+
+    let locations = heatmap::locations(&match_, &filter);
+
+    Ok((http::ContentType::SVG, HeatmapRenderer::default().render_svg(&projection, &locations)))
+}
+
+#[get("/<match_id>/sides")]
+async fn get_sides(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, match_id: &str) -> Json<SideReport> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let mut match_ = Match::from_id(&client, &auth_data, match_id).await.unwrap();
+    cache.fill_names(&client, &auth_data, &mut [&mut match_]).await.unwrap();
+
+    Json(analytics::sides(&match_))
+}
+
+#[get("/<match_id>/timeline")]
+async fn get_timeline(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, match_id: &str) -> Json<Vec<TimelineEntry>> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let mut match_ = Match::from_id(&client, &auth_data, match_id).await.unwrap();
+    cache.fill_names(&client, &auth_data, &mut [&mut match_]).await.unwrap();
+
+    Json(timeline::timeline(&match_))
+}
+
+#[get("/<match_id>/timeline.ndjson")]
+async fn get_timeline_ndjson(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, match_id: &str) -> (http::ContentType, String) {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let mut match_ = Match::from_id(&client, &auth_data, match_id).await.unwrap();
+    cache.fill_names(&client, &auth_data, &mut [&mut match_]).await.unwrap();
+
+    let ndjson = timeline::to_ndjson(&timeline::timeline(&match_)).unwrap();
+
+    (http::ContentType::new("application", "x-ndjson"), ndjson)
+}
+
+/// `player_id` is either a PUUID or a `GameName#Tag` Riot ID. `start` and `end` index into the history
+/// and may be at most `export::MAX_EXPORT_MATCHES` apart. `format` is `csv` (default) or `parquet`.
+#[get("/<player_id>?<start>&<end>&<queue>&<format>")]
+async fn export_history(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, player_id: &str, start: Option<usize>, end: Option<usize>, queue: Option<&str>, format: Option<&str>) -> Result<(http::ContentType, Vec<u8>), Status> {
+    let format = format.unwrap_or("csv").parse::<ExportFormat>().map_err(|_| Status::BadRequest)?;
+    let start = start.unwrap_or(0);
+    let end = end.unwrap_or(start + history::HISTORY_PAGE_SIZE);
+
+    if end < start || end - start > export::MAX_EXPORT_MATCHES {
+        return Err(Status::BadRequest);
+    }
+
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let player_id = player::resolve_player_id(&client, &auth_data, cache, player_id).await.unwrap();
+
+    let queue = queue.map(QueueId::from);
+
+    let history = history::get_match_history(&client, &auth_data, &player_id, queue.as_ref(), start, Some(end)).await.unwrap();
+
+    let tables = Tables::from_matches(history.iter().filter_map(|entry| entry.game_data.as_ref()));
+
+    Ok((http::ContentType::ZIP, tables.to_zip(format).unwrap()))
+}
+
+/// Fetches matches played since the last sync into the local store.
+/// `player_id` is either a PUUID or a `GameName#Tag` Riot ID.
+#[post("/<player_id>/sync")]
+async fn sync_player(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, limiter: &State<RateLimiter>, store: &State<Store>, player_id: &str) -> Json<SyncReport> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let player_id = player::resolve_player_id(&client, &auth_data, cache, player_id).await.unwrap();
+
+    let report = store::sync(&client, &auth_data, store, &player_id, Some(limiter)).await.unwrap();
+
+    Json(report)
+}
+
+/// Like `/history`, but served from the local store without contacting Riot. Takes a PUUID.
+#[get("/<player_id>/history?<queue>&<start>&<end>")]
+async fn get_stored_history(store: &State<Store>, player_id: String, queue: Option<&str>, start: Option<usize>, end: Option<usize>) -> Json<Vec<MatchHistoryEntry>> {
+    let queue = queue.map(QueueId::from);
+
+    Json(store.run(move |store| store.history(&player_id, queue.as_ref(), start.unwrap_or(0), end)).await.unwrap())
+}
+
+/// Scoreboard lines of a player across all stored matches. Takes a PUUID.
+#[get("/<player_id>/stats")]
+async fn get_stored_stats(store: &State<Store>, player_id: String) -> Json<Vec<PlayerMatchStats>> {
+    Json(store.run(move |store| store.player_stats(&player_id)).await.unwrap())
+}
+
+/// Stats over the player's matches in the local store, so sync them first. `from` and `to` are
+/// Unix milliseconds, `utc_offset` is in minutes and applies to the day of week and hour buckets.
+#[get("/<player_id>?<from>&<to>&<season>&<queue>&<utc_offset>")]
+async fn get_aggregate_stats(store: &State<Store>, player_id: String, from: Option<u64>, to: Option<u64>, season: Option<String>, queue: Option<&str>, utc_offset: Option<i32>) -> Json<AggregateStats> {
+    let filter = StatsFilter {
+        from,
+        to,
+        season_id: season,
+        queue: queue.map(QueueId::from),
+        utc_offset_minutes: utc_offset.unwrap_or(0),
+    };
+
+    let stats = store
+        .run(move |store| {
+            let history = store.history(&player_id, filter.queue.as_ref(), 0, None)?;
+            let matches = history.iter().filter_map(|entry| entry.game_data.as_ref());
+
+            Ok(analytics::aggregate(matches, &player_id, &filter))
+        })
+        .await
+        .unwrap();
+
+    Json(stats)
+}
+
+/// Play sessions over the player's latest `limit` stored matches, with RR from Riot's competitive
+/// updates. `idle_gap` is in minutes.
+#[get("/<player_id>/sessions?<idle_gap>&<limit>")]
+async fn get_sessions(cookie_jar: &CookieJar<'_>, store: &State<Store>, player_id: &str, idle_gap: Option<u64>, limit: Option<usize>) -> Json<SessionReport> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let limit = limit.unwrap_or(100);
+    let owned_id = player_id.to_string();
+    let history = store.run(move |store| store.history(&owned_id, None, 0, Some(limit))).await.unwrap();
+
+    // Only the competitive matches among those have updates. The store may be behind Riot, so page
+    // back until the oldest of them rather than counting them.
+    let oldest_competitive = history.iter().rev().find(|entry| entry.queue_id == QueueId::Competitive);
+    let mut updates = match oldest_competitive {
+        Some(entry) => mmr::get_competitive_updates_through(&client, &auth_data, player_id, &entry.match_id).await.unwrap(),
+        None => Vec::new(),
+    };
+
+    let match_ids: HashSet<&str> = history.iter().map(|entry| entry.match_id.as_str()).collect();
+    updates.retain(|update| match_ids.contains(update.match_id.as_str()));
+
+    let idle_gap = idle_gap.map_or(analytics::DEFAULT_IDLE_GAP, |minutes| minutes.saturating_mul(60 * 1000));
+
+    Json(analytics::sessions(&history, player_id, &updates, idle_gap))
+}
+
+/// `trade_window` is in milliseconds.
+#[get("/<match_id>/duels?<trade_window>")]
+async fn get_duels(cookie_jar: &CookieJar<'_>, cache: &State<PlayerCache>, match_id: &str, trade_window: Option<u32>) -> Json<DuelReport> {
+    let client = Client::new().unwrap();
+
+    let auth_data = serde_json::from_str::<AuthData>(cookie_jar.get("auth_data").unwrap().value()).unwrap();
+
+    let mut match_ = Match::from_id(&client, &auth_data, match_id).await.unwrap();
+    cache.fill_names(&client, &auth_data, &mut [&mut match_]).await.unwrap();
+
+    Json(analytics::duels(&match_, trade_window.unwrap_or(analytics::DEFAULT_TRADE_WINDOW)))
 }
@@ -1,99 +1,807 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
 use crate::auth::AuthData;
+use crate::common;
+use crate::queue::QueueId;
 use crate::requests::*;
 
-use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
 pub type PlayerId = String;
 pub type MapId = String;
+pub type WeaponId = String;
+pub type Extra = serde_json::Map<String, Value>;
+
+/// Riot sends `null` instead of an empty list in modes without rounds, teams or locations.
+fn null_as_default<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Match {
-    pub bots: Vec<Value>,
-    pub coaches: Vec<Value>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub bots: Vec<Bot>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub coaches: Vec<Coach>,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub kills: Vec<Kill>,
 
     #[serde(rename(deserialize = "matchInfo"))]
     pub match_info: MatchInfo,
 
+    #[serde(default, deserialize_with = "null_as_default")]
     pub players: Vec<Player>,
 
-    #[serde(rename(deserialize = "roundResults"))]
+    #[serde(rename(deserialize = "roundResults"), default, deserialize_with = "null_as_default")]
     pub round_results: Vec<RoundResult>,
 
+    #[serde(default, deserialize_with = "null_as_default")]
     pub teams: Vec<Team>,
+
+    /// Fields the model does not know about yet, kept as Riot sent them
+    #[serde(flatten)]
+    pub extra: Extra,
+
+    /// Attacking team by half, worked out on first use
+    #[serde(skip)]
+    attackers: OnceLock<HashMap<u32, TeamColor>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bot {
+    #[serde(rename(deserialize = "subject"))]
+    pub id: PlayerId,
+    #[serde(rename(deserialize = "teamId"))]
+    pub team_id: TeamColor,
+    #[serde(rename(deserialize = "characterId"), default)]
+    pub agent_id: Option<AgentId>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Coach {
+    #[serde(rename(deserialize = "subject"))]
+    pub id: PlayerId,
+    #[serde(rename(deserialize = "teamId"))]
+    pub team_id: TeamColor,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Kill {
+    #[serde(default, deserialize_with = "null_as_default")]
     pub assistants: Vec<PlayerId>,
     pub killer: PlayerId,
     pub victim: PlayerId,
-    // player locations
-    pub round: u32,
 
-    #[serde(rename(deserialize = "roundTime"))]
-    pub round_time: u32,
+    /// Riot leaves out some kill fields now and then, which are None here rather than made up.
+    #[serde(rename(deserialize = "victimLocation"), default)]
+    pub victim_location: Option<Location>,
+    /// Where every living player was standing at the time of the kill, including the killer.
+    #[serde(rename(deserialize = "playerLocations"), default, deserialize_with = "null_as_default")]
+    pub player_locations: Vec<PlayerLocation>,
+
+    #[serde(rename(deserialize = "finishingDamage"), default)]
+    pub finishing_damage: Option<FinishingDamage>,
+
+    #[serde(default)]
+    pub round: Option<u32>,
+
+    #[serde(rename(deserialize = "roundTime"), default)]
+    pub round_time: Option<u32>,
+
+    #[serde(rename(deserialize = "gameTime"), default)]
+    pub game_time: Option<u32>,
+
+    /// Fields the model does not know about yet, kept as Riot sent them
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Kill {
+    pub fn location_of(&self, player_id: &str) -> Option<Location> {
+        self.player_locations
+            .iter()
+            .find(|p| p.player_id == player_id)
+            .map(|p| p.location)
+    }
+
+    pub fn killer_location(&self) -> Option<Location> {
+        self.location_of(&self.killer)
+    }
+}
+
+/// World coordinates, as used by Riot in match details.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub x: f64,
+    pub y: f64,
+}
 
-    #[serde(rename(deserialize = "gameTime"))]
-    pub game_time: u32,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlayerLocation {
+    #[serde(rename(deserialize = "subject"))]
+    pub player_id: PlayerId,
+    #[serde(rename(deserialize = "viewRadians"))]
+    pub view_radians: f64,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AbilitySlot {
+    Grenade,
+    Ability1,
+    Ability2,
+    Ultimate,
+    Other(String),
+}
+
+/// What dealt the final blow. Weapon IDs are lowercased so they match content and economy IDs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DamageSource {
+    Weapon(WeaponId),
+    Ability(AbilitySlot),
+    Bomb,
+    Fall,
+    Melee,
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawFinishingDamage")]
+pub struct FinishingDamage {
+    pub source: DamageSource,
+    pub secondary_fire: bool,
+}
+
+#[derive(Deserialize)]
+struct RawFinishingDamage {
+    #[serde(rename = "damageType")]
+    damage_type: String,
+    #[serde(rename = "damageItem", default)]
+    damage_item: String,
+    #[serde(rename = "isSecondaryFireMode", default)]
+    is_secondary_fire_mode: bool,
+}
+
+impl DamageSource {
+    /// Damage type and item in Riot's terms, e.g. `("Ability", "Ultimate")`.
+    pub fn parts(&self) -> (&str, &str) {
+        match self {
+            DamageSource::Weapon(weapon) => ("Weapon", weapon),
+            DamageSource::Ability(slot) => ("Ability", match slot {
+                AbilitySlot::Grenade => "GrenadeAbility",
+                AbilitySlot::Ability1 => "Ability1",
+                AbilitySlot::Ability2 => "Ability2",
+                AbilitySlot::Ultimate => "Ultimate",
+                AbilitySlot::Other(item) => item,
+            }),
+            DamageSource::Bomb => ("Bomb", ""),
+            DamageSource::Fall => ("Fall", ""),
+            DamageSource::Melee => ("Melee", ""),
+            DamageSource::Unknown(damage_type) => (damage_type, ""),
+        }
+    }
+}
+
+impl From<RawFinishingDamage> for FinishingDamage {
+    fn from(damage: RawFinishingDamage) -> Self {
+        let source = match damage.damage_type.as_str() {
+            "Weapon" => DamageSource::Weapon(damage.damage_item.to_lowercase()),
+            "Ability" => DamageSource::Ability(match damage.damage_item.as_str() {
+                "GrenadeAbility" => AbilitySlot::Grenade,
+                "Ability1" => AbilitySlot::Ability1,
+                "Ability2" => AbilitySlot::Ability2,
+                "Ultimate" => AbilitySlot::Ultimate,
+                _ => AbilitySlot::Other(damage.damage_item),
+            }),
+            "Bomb" => DamageSource::Bomb,
+            "Fall" => DamageSource::Fall,
+            "Melee" => DamageSource::Melee,
+            _ => DamageSource::Unknown(damage.damage_type),
+        };
+
+        Self {
+            source,
+            secondary_fire: damage.is_secondary_fire_mode,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MatchInfo {
-    #[serde(rename(deserialize = "gameLengthMillis"))]
+    #[serde(rename(deserialize = "matchId"))]
+    pub match_id: String,
+    #[serde(rename(deserialize = "gameStartMillis"))]
+    pub game_start: u64,
+    #[serde(rename(deserialize = "seasonId"), default)]
+    pub season_id: String,
+    /// Asset path of the game mode, e.g. `/Game/GameModes/Bomb/BombGameMode.BombGameMode_C`
+    #[serde(rename(deserialize = "gameMode"), default)]
+    pub game_mode: String,
+    #[serde(rename(deserialize = "gameLengthMillis"), default)]
     pub game_length: u64,
     #[serde(rename(deserialize = "queueID"))]
-    pub queue_id: String,
+    pub queue_id: QueueId,
     #[serde(rename(deserialize = "mapId"))]
     pub map_id: MapId,
-    #[serde(rename(deserialize = "isRanked"))]
+    #[serde(rename(deserialize = "isRanked"), default)]
     pub is_ranked: bool,
+
+    /// Fields the model does not know about yet, kept as Riot sent them
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-// missing things: behavioural factors, player card, player title, level border
+pub type AgentId = String;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Player {
-    #[serde(rename(deserialize = "accountLevel"))]
+    #[serde(rename(deserialize = "accountLevel"), default)]
     pub level: u32,
     #[serde(rename(deserialize = "subject"))]
     pub id: PlayerId,
 
-    #[serde(rename(deserialize = "gameName"))]
+    #[serde(rename(deserialize = "gameName"), default)]
     pub ign: String,
 
-    #[serde(rename(deserialize = "tagLine"))]
+    #[serde(rename(deserialize = "tagLine"), default)]
     pub tagline: String,
 
-    #[serde(rename(deserialize = "competitiveTier"))]
+    #[serde(rename(deserialize = "competitiveTier"), default)]
     pub rank: u32,
+
+    #[serde(rename(deserialize = "teamId"))]
+    pub team_id: TeamColor,
+    #[serde(rename(deserialize = "characterId"), default)]
+    pub agent_id: AgentId,
+    #[serde(rename(deserialize = "partyId"), default)]
+    pub party_id: String,
+
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub stats: PlayerStats,
+
+    #[serde(rename(deserialize = "playerCard"), default)]
+    pub card: String,
+    #[serde(rename(deserialize = "playerTitle"), default)]
+    pub title: String,
+    #[serde(rename(deserialize = "preferredLevelBorder"), default)]
+    pub level_border: String,
+
+    #[serde(rename(deserialize = "behaviorFactors"), default)]
+    pub behavior_factors: Option<BehaviorFactors>,
+
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerStats {
+    pub score: u32,
+    #[serde(rename(deserialize = "roundsPlayed"))]
+    pub rounds_played: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    #[serde(rename(deserialize = "playtimeMillis"))]
+    pub playtime: u64,
+
+    #[serde(rename(deserialize = "abilityCasts"), default)]
+    pub ability_casts: Option<AbilityCasts>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+pub struct AbilityCasts {
+    #[serde(rename(deserialize = "grenadeCasts"))]
+    pub grenade: u32,
+    #[serde(rename(deserialize = "ability1Casts"))]
+    pub ability1: u32,
+    #[serde(rename(deserialize = "ability2Casts"))]
+    pub ability2: u32,
+    #[serde(rename(deserialize = "ultimateCasts"))]
+    pub ultimate: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BehaviorFactors {
+    #[serde(rename(deserialize = "afkRounds"))]
+    pub afk_rounds: f64,
+    pub collisions: f64,
+    #[serde(rename(deserialize = "commsRatingRecovery"))]
+    pub comms_rating_recovery: f64,
+    #[serde(rename(deserialize = "damageParticipationOutgoing"))]
+    pub damage_participation_outgoing: f64,
+    #[serde(rename(deserialize = "friendlyFireIncoming"))]
+    pub friendly_fire_incoming: f64,
+    #[serde(rename(deserialize = "friendlyFireOutgoing"))]
+    pub friendly_fire_outgoing: f64,
+    #[serde(rename(deserialize = "mouseMovement"))]
+    pub mouse_movement: f64,
+    #[serde(rename(deserialize = "stayedInSpawnRounds"))]
+    pub stayed_in_spawn_rounds: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String")]
+pub enum RoundResultCode {
+    Elimination,
+    Detonate,
+    Defuse,
+    Surrendered,
+    /// Riot left the code empty, so how the round ended isn't known.
+    Unknown,
+    Other(String),
+}
+
+impl RoundResultCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RoundResultCode::Elimination => "Elimination",
+            RoundResultCode::Detonate => "Detonate",
+            RoundResultCode::Defuse => "Defuse",
+            RoundResultCode::Surrendered => "Surrendered",
+            RoundResultCode::Unknown => "",
+            RoundResultCode::Other(code) => code,
+        }
+    }
+}
+
+impl From<String> for RoundResultCode {
+    fn from(code: String) -> Self {
+        match code.as_str() {
+            "Elimination" => RoundResultCode::Elimination,
+            "Detonate" => RoundResultCode::Detonate,
+            "Defuse" => RoundResultCode::Defuse,
+            "Surrendered" => RoundResultCode::Surrendered,
+            "" => RoundResultCode::Unknown,
+            _ => RoundResultCode::Other(code),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpikePlant {
+    pub planter: PlayerId,
+    pub round_time: u32,
+    pub location: Location,
+    pub player_locations: Vec<PlayerLocation>,
+    pub site: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpikeDefuse {
+    pub defuser: PlayerId,
+    pub round_time: u32,
+    pub location: Location,
+    pub player_locations: Vec<PlayerLocation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(from = "RawRoundResult")]
 pub struct RoundResult {
-    #[serde(rename(deserialize = "winningTeam"))]
-    pub winning_team: String,
+    pub round_num: u32,
+    pub result_code: RoundResultCode,
+    pub winning_team: TeamColor,
+    pub plant: Option<SpikePlant>,
+    pub defuse: Option<SpikeDefuse>,
+    pub player_stats: Vec<PlayerRoundStats>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlayerRoundStats {
+    #[serde(rename(deserialize = "subject"))]
+    pub player_id: PlayerId,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub kills: Vec<Kill>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub damage: Vec<Damage>,
+    #[serde(default)]
+    pub score: u32,
+    #[serde(default)]
+    pub economy: Option<Economy>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Damage {
+    pub receiver: PlayerId,
+    pub damage: u32,
+    pub headshots: u32,
+    pub bodyshots: u32,
+    pub legshots: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Economy {
+    #[serde(rename(deserialize = "loadoutValue"))]
+    pub loadout_value: u32,
+    pub weapon: WeaponId,
+    pub armor: String,
+    pub spent: u32,
+    pub remaining: u32,
+}
+
+#[derive(Deserialize)]
+struct RawRoundResult {
+    #[serde(rename = "roundNum")]
+    round_num: u32,
+    #[serde(rename = "roundResultCode", default)]
+    round_result_code: String,
+    #[serde(rename = "winningTeam")]
+    winning_team: TeamColor,
+
+    #[serde(rename = "bombPlanter")]
+    bomb_planter: Option<PlayerId>,
+    #[serde(rename = "plantRoundTime", default)]
+    plant_round_time: u32,
+    #[serde(rename = "plantLocation")]
+    plant_location: Option<Location>,
+    #[serde(rename = "plantPlayerLocations")]
+    plant_player_locations: Option<Vec<PlayerLocation>>,
+    #[serde(rename = "plantSite", default)]
+    plant_site: String,
+
+    #[serde(rename = "bombDefuser")]
+    bomb_defuser: Option<PlayerId>,
+    #[serde(rename = "defuseRoundTime", default)]
+    defuse_round_time: u32,
+    #[serde(rename = "defuseLocation")]
+    defuse_location: Option<Location>,
+    #[serde(rename = "defusePlayerLocations")]
+    defuse_player_locations: Option<Vec<PlayerLocation>>,
+
+    #[serde(rename = "playerStats", default, deserialize_with = "null_as_default")]
+    player_stats: Vec<PlayerRoundStats>,
+
+    #[serde(flatten)]
+    extra: Extra,
+}
+
+impl From<RawRoundResult> for RoundResult {
+    fn from(round: RawRoundResult) -> Self {
+        let origin = Location { x: 0.0, y: 0.0 };
+
+        let plant = round.bomb_planter.map(|planter| SpikePlant {
+            planter,
+            round_time: round.plant_round_time,
+            location: round.plant_location.unwrap_or(origin),
+            player_locations: round.plant_player_locations.unwrap_or_default(),
+            site: round.plant_site,
+        });
+
+        let defuse = round.bomb_defuser.map(|defuser| SpikeDefuse {
+            defuser,
+            round_time: round.defuse_round_time,
+            location: round.defuse_location.unwrap_or(origin),
+            player_locations: round.defuse_player_locations.unwrap_or_default(),
+        });
+
+        Self {
+            round_num: round.round_num,
+            result_code: round.round_result_code.into(),
+            winning_team: round.winning_team,
+            plant,
+            defuse,
+            player_stats: round.player_stats,
+            extra: round.extra,
+        }
+    }
+}
+
+impl RoundResult {
+    pub fn stats_for(&self, player_id: &str) -> Option<&PlayerRoundStats> {
+        self.player_stats.iter().find(|s| s.player_id == player_id)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Team {
     #[serde(rename(deserialize = "teamId"))]
-    side: String,
-    #[serde(rename(deserialize = "roundsWon"))]
-    rounds_won: u32,
-    won: bool,
+    pub team_id: TeamColor,
+    #[serde(rename(deserialize = "roundsPlayed"), default)]
+    pub rounds_played: u32,
+    #[serde(rename(deserialize = "roundsWon"), default)]
+    pub rounds_won: u32,
+    /// Rounds won in round based modes, kills or score otherwise
+    #[serde(rename(deserialize = "numPoints"), default)]
+    pub num_points: u32,
+    #[serde(default)]
+    pub won: bool,
+
+    /// Fields the model does not know about yet, kept as Riot sent them
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Layout of a match, derived from the game mode asset path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum GameMode {
+    /// Competitive, unrated, premier and most custom games
+    Standard,
+    Swiftplay,
+    SpikeRush,
+    Replication,
+    Deathmatch,
+    TeamDeathmatch,
+    Escalation,
+    Snowball,
+    Unknown,
+}
+
+impl GameMode {
+    pub fn from_asset_path(path: &str) -> Self {
+        let name = path.rsplit('/').next().unwrap_or_default();
+
+        if name.starts_with("BombGameMode") {
+            GameMode::Standard
+        } else if name.starts_with("Swiftplay") {
+            GameMode::Swiftplay
+        } else if name.starts_with("QuickBombGameMode") {
+            GameMode::SpikeRush
+        } else if name.starts_with("OneForAll") {
+            GameMode::Replication
+        } else if name.starts_with("DeathmatchGameMode") {
+            GameMode::Deathmatch
+        } else if name.starts_with("HURMGameMode") {
+            GameMode::TeamDeathmatch
+        } else if name.starts_with("GunGame") {
+            GameMode::Escalation
+        } else if name.starts_with("SnowballFight") {
+            GameMode::Snowball
+        } else {
+            GameMode::Unknown
+        }
+    }
+
+    /// Rounds before teams swap sides, for modes played in attack and defense rounds.
+    pub fn half_length(&self) -> Option<u32> {
+        match self {
+            GameMode::Standard | GameMode::Unknown => Some(HALF_LENGTH),
+            GameMode::Swiftplay | GameMode::Replication => Some(4),
+            GameMode::SpikeRush => Some(3),
+            _ => None,
+        }
+    }
+
+    pub fn is_free_for_all(&self) -> bool {
+        *self == GameMode::Deathmatch
+    }
+
+    /// Whether team scores count rounds won rather than kills or points.
+    pub fn is_round_based(&self) -> bool {
+        self.half_length().is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamScore {
+    pub team_id: TeamColor,
+    pub points: u32,
+    pub won: bool,
+}
+
+/// Final standing of a player in a free for all match.
+#[derive(Debug, Clone, Serialize)]
+pub struct Placement {
+    pub place: u32,
+    pub player_id: PlayerId,
+    pub points: u32,
+    pub kills: u32,
+    pub deaths: u32,
+}
+
+/// Rounds in each regulation half. Every round after the second half is overtime.
+pub const HALF_LENGTH: u32 = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub enum TeamColor {
     Red,
-    Blue
+    Blue,
+    /// Free for all modes put every player on a team named after their own ID
+    Other(String),
+}
+
+impl TeamColor {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TeamColor::Red => "Red",
+            TeamColor::Blue => "Blue",
+            TeamColor::Other(team) => team,
+        }
+    }
+
+    pub fn opponent(&self) -> Option<TeamColor> {
+        match self {
+            TeamColor::Red => Some(TeamColor::Blue),
+            TeamColor::Blue => Some(TeamColor::Red),
+            TeamColor::Other(_) => None,
+        }
+    }
+
+    /// Red starts on attack, teams swap at half time and again after every overtime round.
+    pub fn scheduled_attacker(round: u32, half_length: u32) -> TeamColor {
+        let red_attacks = if round < half_length {
+            true
+        } else if round < half_length * 2 {
+            false
+        } else {
+            (round - half_length * 2).is_multiple_of(2)
+        };
+
+        if red_attacks { TeamColor::Red } else { TeamColor::Blue }
+    }
+}
+
+/// Index of the half a round belongs to. Every overtime round is a half of its own.
+fn half_of(round: u32, half_length: u32) -> u32 {
+    if round < half_length * 2 {
+        round / half_length
+    } else {
+        round - half_length * 2 + 2
+    }
+}
+
+impl From<String> for TeamColor {
+    fn from(team: String) -> Self {
+        match team.as_str() {
+            "Red" => TeamColor::Red,
+            "Blue" => TeamColor::Blue,
+            _ => TeamColor::Other(team),
+        }
+    }
+}
+
+impl From<TeamColor> for String {
+    fn from(team: TeamColor) -> Self {
+        team.as_str().to_string()
+    }
+}
+
+impl PartialEq<&str> for TeamColor {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Side {
+    Attack,
+    Defense,
+}
+
+impl FromStr for Side {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "attack" | "attackers" => Ok(Side::Attack),
+            "defense" | "defenders" => Ok(Side::Defense),
+            _ => Err(anyhow!("Unknown side {}", s)),
+        }
+    }
 }
 
 impl Match {
+    pub fn mode(&self) -> GameMode {
+        GameMode::from_asset_path(&self.match_info.game_mode)
+    }
+
+    /// Rounds won per team in round based modes, otherwise Riot's points, highest first.
+    pub fn scores(&self) -> Vec<TeamScore> {
+        let round_based = self.mode().is_round_based();
+
+        let mut scores: Vec<TeamScore> = self
+            .teams
+            .iter()
+            .map(|team| TeamScore {
+                team_id: team.team_id.clone(),
+                points: if round_based { team.rounds_won } else { team.num_points },
+                won: team.won,
+            })
+            .collect();
+
+        scores.sort_by_key(|score| std::cmp::Reverse(score.points));
+
+        scores
+    }
+
+    /// Standings in free for all modes, where every player is their own team.
+    /// Ties on points go to the player with fewer deaths.
+    pub fn placements(&self) -> Vec<Placement> {
+        let mut placements: Vec<Placement> = self
+            .players
+            .iter()
+            .map(|player| {
+                let points = self
+                    .teams
+                    .iter()
+                    .find(|team| team.team_id == player.team_id)
+                    .map_or(player.stats.kills, |team| team.num_points);
+
+                Placement {
+                    place: 0,
+                    player_id: player.id.clone(),
+                    points,
+                    kills: player.stats.kills,
+                    deaths: player.stats.deaths,
+                }
+            })
+            .collect();
+
+        placements.sort_by(|a, b| b.points.cmp(&a.points).then(a.deaths.cmp(&b.deaths)));
+
+        for (i, placement) in placements.iter_mut().enumerate() {
+            placement.place = i as u32 + 1;
+        }
+
+        placements
+    }
+
+    /// `GameName#Tag` of a player, unless Riot left them unnamed.
+    pub fn riot_id(&self, player_id: &str) -> Option<String> {
+        self.players
+            .iter()
+            .find(|p| p.id == player_id && !p.ign.is_empty())
+            .map(|p| format!("{}#{}", p.ign, p.tagline))
+    }
+
+    pub fn team_of(&self, player_id: &str) -> Option<&TeamColor> {
+        self.players.iter().find(|p| p.id == player_id).map(|p| &p.team_id)
+    }
+
+    fn half_length(&self) -> u32 {
+        self.mode().half_length().unwrap_or(HALF_LENGTH)
+    }
+
+    /// The team of the first planter in each half that saw a plant.
+    fn attackers(&self) -> &HashMap<u32, TeamColor> {
+        self.attackers.get_or_init(|| {
+            let half_length = self.half_length();
+            let mut attackers = HashMap::new();
+
+            for round in self.round_results.iter() {
+                if let Some(team) = round.plant.as_ref().and_then(|plant| self.team_of(&plant.planter)) {
+                    attackers.entry(half_of(round.round_num, half_length)).or_insert_with(|| team.clone());
+                }
+            }
+
+            attackers
+        })
+    }
+
+    /// The team that planted anywhere in the round's half, otherwise the regulation side schedule.
+    pub fn attacking_team(&self, round: u32) -> TeamColor {
+        let half_length = self.half_length();
+
+        self.attackers()
+            .get(&half_of(round, half_length))
+            .cloned()
+            .unwrap_or_else(|| TeamColor::scheduled_attacker(round, half_length))
+    }
+
+    pub fn side_of(&self, team: &TeamColor, round: u32) -> Side {
+        if self.attacking_team(round) == *team { Side::Attack } else { Side::Defense }
+    }
+
     pub async fn from_id(client: &Client, auth_data: &AuthData, game_id: &str) -> Result<Self> {
+        Match::from_json(&Match::fetch_json(client, auth_data, game_id).await?)
+    }
+
+    /// Match details exactly as Riot sent them, for storing and parsing again later. Error statuses,
+    /// such as rate limits, fail here rather than being returned as the body.
+    pub async fn fetch_json(client: &Client, auth_data: &AuthData, game_id: &str) -> Result<String> {
         let url = format!(
             "https://pd.{}.a.pvp.net/match-details/v1/matches/{}",
             "na", game_id
@@ -101,11 +809,255 @@ impl Match {
 
         let mut req = Request::new(client, url, Method::GET);
         req.append_headers(auth_data.get_auth_headers());
+        req.append_headers(common::get_client_headers().await?);
 
         let res = req.send().await?;
 
-        let data = res.json::<Match>().await?;
+        Ok(res.error_for_status()?.text().await?)
+    }
+
+    /// Parses match details, logging fields the models are missing or do not know yet.
+    pub fn from_json(body: &str) -> Result<Self> {
+        let match_: Match = serde_json::from_str(body).map_err(|err| {
+            log::warn!(
+                "match details no longer fit the model: {} (sample: {})",
+                err,
+                excerpt(body, err.line(), err.column())
+            );
+            err
+        })?;
+
+        let mut seen = UNKNOWN_FIELDS.get_or_init(Default::default).lock().unwrap();
+
+        for (path, value) in match_.unknown_fields() {
+            if seen.insert(path.clone()) {
+                log::warn!(
+                    "match {} has unknown field {} (sample: {})",
+                    match_.match_info.match_id,
+                    path,
+                    excerpt(&value.to_string(), 1, 1)
+                );
+            }
+        }
+
+        Ok(match_)
+    }
+
+    /// Fields captured in `extra` anywhere in the match, by path, with the first value seen.
+    pub fn unknown_fields(&self) -> BTreeMap<String, &Value> {
+        let extras = [("", &self.extra), ("matchInfo.", &self.match_info.extra)]
+            .into_iter()
+            .chain(self.players.iter().map(|player| ("players[].", &player.extra)))
+            .chain(self.kills.iter().map(|kill| ("kills[].", &kill.extra)))
+            .chain(self.round_results.iter().map(|round| ("roundResults[].", &round.extra)))
+            .chain(self.teams.iter().map(|team| ("teams[].", &team.extra)));
+
+        let mut fields = BTreeMap::new();
+
+        for (prefix, extra) in extras {
+            for (key, value) in extra {
+                fields.entry(format!("{}{}", prefix, key)).or_insert(value);
+            }
+        }
+
+        fields
+    }
+}
+
+/// Unknown field paths already logged, so each is only reported once per run.
+static UNKNOWN_FIELDS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+const EXCERPT_LENGTH: usize = 200;
+
+/// Up to `EXCERPT_LENGTH` characters of `text` around a 1-based line and column.
+fn excerpt(text: &str, line: usize, column: usize) -> String {
+    let line: Vec<char> = text.lines().nth(line.saturating_sub(1)).unwrap_or_default().chars().collect();
+    let start = column.saturating_sub(EXCERPT_LENGTH / 2).min(line.len());
+    let end = (start + EXCERPT_LENGTH).min(line.len());
+
+    line[start..end].iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH: &str = include_str!("../tests/fixtures/match.json");
+
+    #[test]
+    fn test_deserialize_players() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+
+        let player = &match_.players[0];
+
+        assert_eq!(player.ign, "Alpha");
+        assert_eq!(player.team_id, TeamColor::Red);
+        assert_eq!(player.agent_id, "add6443a-41bd-e414-f6ad-e58d267f4e95");
+        assert_eq!(player.stats.kills, 2);
+        assert_eq!(player.stats.ability_casts.as_ref().unwrap().grenade, 1);
+        assert_eq!(player.card, "card-1");
+        assert!(player.behavior_factors.is_some());
+    }
+
+    #[test]
+    fn test_scheduled_attacker() {
+        assert_eq!(TeamColor::scheduled_attacker(0, HALF_LENGTH), TeamColor::Red);
+        assert_eq!(TeamColor::scheduled_attacker(11, HALF_LENGTH), TeamColor::Red);
+        assert_eq!(TeamColor::scheduled_attacker(12, HALF_LENGTH), TeamColor::Blue);
+        assert_eq!(TeamColor::scheduled_attacker(23, HALF_LENGTH), TeamColor::Blue);
+        assert_eq!(TeamColor::scheduled_attacker(24, HALF_LENGTH), TeamColor::Red);
+        assert_eq!(TeamColor::scheduled_attacker(25, HALF_LENGTH), TeamColor::Blue);
+        assert_eq!(TeamColor::scheduled_attacker(3, 3), TeamColor::Blue);
+    }
+
+    const DEATHMATCH: &str = include_str!("../tests/fixtures/deathmatch.json");
+
+    #[test]
+    fn test_game_mode() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        assert_eq!(match_.mode(), GameMode::Standard);
+        assert_eq!(match_.match_info.queue_id, QueueId::Competitive);
+
+        assert_eq!(
+            GameMode::from_asset_path("/Game/GameModes/QuickBomb/QuickBombGameMode.QuickBombGameMode_C"),
+            GameMode::SpikeRush
+        );
+        assert_eq!(
+            GameMode::from_asset_path("/Game/GameModes/GunGame/GunGameTeamsGameMode.GunGameTeamsGameMode_C"),
+            GameMode::Escalation
+        );
+    }
+
+    #[test]
+    fn test_deserialize_coaches() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+
+        assert_eq!(match_.coaches[0].team_id, TeamColor::Blue);
+        assert!(match_.bots.is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_deathmatch() {
+        let match_: Match = serde_json::from_str(DEATHMATCH).unwrap();
+
+        assert_eq!(match_.mode(), GameMode::Deathmatch);
+        assert_eq!(match_.match_info.queue_id, QueueId::Deathmatch);
+        assert!(match_.round_results.is_empty());
+        assert!(match_.kills[0].player_locations.is_empty());
+
+        let placements = match_.placements();
+        assert_eq!(placements[0].player_id, "00000000-0000-0000-0000-00000000000b");
+        assert_eq!((placements[1].place, placements[1].points, placements[1].deaths), (2, 30, 20));
+        assert_eq!(placements[2].points, 30);
+
+        assert_eq!(match_.scores()[0].points, 40);
+    }
+
+    #[test]
+    fn test_half_of() {
+        assert_eq!(half_of(11, HALF_LENGTH), 0);
+        assert_eq!(half_of(12, HALF_LENGTH), 1);
+        assert_eq!(half_of(24, HALF_LENGTH), 2);
+        assert_eq!(half_of(25, HALF_LENGTH), 3);
+    }
+
+    #[test]
+    fn test_attacking_team_per_half() {
+        let mut match_: Match = serde_json::from_str(MATCH).unwrap();
+
+        // Teams attacking the other way around, seen only through a Blue plant in the last round
+        match_.round_results[0].plant = None;
+        match_.round_results[2].plant.as_mut().unwrap().planter = "00000000-0000-0000-0000-000000000003".to_string();
+
+        for round in 0..3 {
+            assert_eq!(match_.attacking_team(round), TeamColor::Blue);
+            assert_eq!(match_.side_of(&TeamColor::Red, round), Side::Defense);
+        }
+
+        assert_eq!(match_.attacking_team(12), TeamColor::Blue);
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let fields = match_.unknown_fields();
+
+        assert_eq!(fields["matchInfo.gamePodId"].as_str(), match_.match_info.extra["gamePodId"].as_str());
+        assert!(fields.contains_key("players[].sessionPlaytimeMinutes"));
+        assert!(fields.contains_key("roundResults[].roundCeremony"));
+        assert!(!fields.keys().any(|path| path.starts_with("kills[]")));
+    }
+
+    #[test]
+    fn test_from_json_tolerates_drift() {
+        let mut value: Value = serde_json::from_str(MATCH).unwrap();
+        let kill = value["kills"][0].as_object_mut().unwrap();
+        kill.remove("roundTime");
+        kill.insert("killerLocation".to_string(), serde_json::json!({ "x": 1, "y": 2 }));
+
+        let match_ = Match::from_json(&value.to_string()).unwrap();
+
+        assert_eq!(match_.kills[0].round_time, None);
+        assert!(match_.kills[0].extra.contains_key("killerLocation"));
+
+        value.as_object_mut().unwrap().remove("matchInfo");
+        assert!(Match::from_json(&value.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_excerpt() {
+        let text = format!("{{\n{}", "a".repeat(300));
+
+        assert_eq!(excerpt(&text, 2, 150).len(), EXCERPT_LENGTH);
+        assert_eq!(excerpt(&text, 2, 290).len(), 110);
+        assert_eq!(excerpt(&text, 5, 1), "");
+    }
+
+    #[test]
+    fn test_deserialize_teams() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+
+        assert_eq!(match_.teams[1].team_id, TeamColor::Blue);
+        assert!(match_.teams[1].won);
+        assert_eq!(match_.round_results[0].winning_team, TeamColor::Red);
+        assert_eq!(match_.side_of(&TeamColor::Blue, 2), Side::Defense);
+        assert_eq!(serde_json::to_value(&match_.teams[0].team_id).unwrap(), "Red");
+    }
+
+    #[test]
+    fn test_deserialize_kills() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+
+        let kill = &match_.kills[0];
+
+        assert_eq!(kill.finishing_damage.as_ref().unwrap().source, DamageSource::Weapon("1baa85b4-4c70-1284-64bb-6481dfc3bb4e".to_string()));
+        assert_eq!(kill.victim_location, Some(Location { x: 1200.0, y: -6000.0 }));
+        assert_eq!((kill.round, kill.round_time), (Some(0), Some(20000)));
+        assert_eq!(kill.killer_location(), Some(Location { x: 800.0, y: -5600.0 }));
+        assert!(kill.location_of(&kill.victim).is_none());
+
+        assert_eq!(match_.kills[2].finishing_damage.as_ref().unwrap().source, DamageSource::Ability(AbilitySlot::Ability1));
+    }
+
+    #[test]
+    fn test_deserialize_round_results() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+
+        let detonated = &match_.round_results[0];
+        assert_eq!(detonated.result_code, RoundResultCode::Detonate);
+        assert_eq!(detonated.plant.as_ref().unwrap().site, "A");
+        assert!(detonated.defuse.is_none());
+
+        let eliminated = &match_.round_results[1];
+        assert_eq!(eliminated.result_code, RoundResultCode::Elimination);
+        assert!(eliminated.plant.is_none());
+
+        let defused = &match_.round_results[2];
+        assert_eq!(defused.defuse.as_ref().unwrap().round_time, 68000);
 
-        Ok(data)
+        let stats = detonated.stats_for(&match_.players[0].id).unwrap();
+        assert_eq!(stats.kills.len(), 2);
+        assert_eq!(stats.damage[0].headshots, 1);
+        assert_eq!(stats.economy.as_ref().unwrap().loadout_value, 800);
     }
 }
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::auth::AuthData;
+use crate::common;
+use crate::content::{self, Catalog, ContentType};
+use crate::queue::QueueId;
+use crate::r#match::MapId;
+use crate::requests::*;
+
+/// Riot returns at most this many competitive updates per request.
+pub const COMPETITIVE_UPDATES_PAGE_SIZE: usize = 20;
+
+#[derive(Debug, Serialize)]
+pub struct CompetitiveUpdate {
+    pub match_id: String,
+    pub map_id: MapId,
+    pub season_id: String,
+    pub start_time: u64,
+    pub tier_before: u32,
+    pub tier_after: u32,
+    pub rr_before: u32,
+    pub rr_after: u32,
+    pub rr_earned: i32,
+    pub performance_bonus: i32,
+    pub afk_penalty: i32,
+}
+
+impl From<response::CompetitiveUpdate> for CompetitiveUpdate {
+    fn from(update: response::CompetitiveUpdate) -> Self {
+        Self {
+            match_id: update.match_id,
+            map_id: update.map_id,
+            season_id: update.season_id,
+            start_time: update.match_start_time,
+            tier_before: update.tier_before_update,
+            tier_after: update.tier_after_update,
+            rr_before: update.ranked_rating_before_update,
+            rr_after: update.ranked_rating_after_update,
+            rr_earned: update.ranked_rating_earned,
+            performance_bonus: update.ranked_rating_performance_bonus,
+            afk_penalty: update.afk_penalty,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeasonSummary {
+    pub season_id: String,
+    pub season_name: Option<String>,
+    /// When the act started, as an ISO 8601 timestamp
+    pub start_time: Option<String>,
+    pub games: u32,
+    pub wins: u32,
+    pub final_tier: u32,
+    pub ranked_rating: u32,
+    pub peak_tier: Option<u32>,
+    pub leaderboard_rank: Option<u32>,
+}
+
+impl SeasonSummary {
+    pub fn resolve_name(&mut self, catalog: &Catalog) {
+        self.season_name = catalog.qualified_name(&self.season_id);
+    }
+}
+
+impl From<response::SeasonalInfo> for SeasonSummary {
+    fn from(info: response::SeasonalInfo) -> Self {
+        let peak_tier = info
+            .wins_by_tier
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, wins)| *wins > 0)
+            .filter_map(|(tier, _)| tier.parse().ok())
+            .max();
+
+        Self {
+            season_id: info.season_id,
+            season_name: None,
+            start_time: None,
+            games: info.number_of_games,
+            wins: info.number_of_wins,
+            final_tier: info.competitive_tier,
+            ranked_rating: info.ranked_rating,
+            peak_tier,
+            leaderboard_rank: Some(info.leaderboard_rank).filter(|rank| *rank > 0),
+        }
+    }
+}
+
+/// Competitive summary of every season the player has played, oldest act first, without season names.
+pub async fn get_season_summaries(client: &Client, auth_data: &AuthData, player_id: &str) -> Result<Vec<SeasonSummary>> {
+    let url = format!("https://pd.{}.a.pvp.net/mmr/v1/players/{}", "na", player_id);
+
+    let mut req = Request::new(client, url, Method::GET);
+    req.append_headers(auth_data.get_auth_headers());
+    req.append_headers(common::get_client_headers().await?);
+
+    let res = req.send().await?;
+
+    let mut data: response::Mmr = res.json().await?;
+
+    let seasons = data
+        .queue_skills
+        .remove("competitive")
+        .and_then(|queue| queue.seasonal_info_by_season_id)
+        .unwrap_or_default();
+
+    let mut summaries: Vec<SeasonSummary> = seasons.into_values().map(|s| s.into()).collect();
+
+    let start_times: HashMap<String, String> = content::get_content::<response::SeasonContent>(client, ContentType::Seasons)
+        .await?
+        .into_iter()
+        .filter_map(|season| Some((season.uuid.to_lowercase(), season.start_time?)))
+        .collect();
+
+    sort_by_start_time(&mut summaries, &start_times);
+
+    Ok(summaries)
+}
+
+/// Fills in act start times, keyed by lowercase season ID, and sorts the oldest act first.
+/// Seasons without a known start time go last.
+fn sort_by_start_time(summaries: &mut [SeasonSummary], start_times: &HashMap<String, String>) {
+    for summary in summaries.iter_mut() {
+        summary.start_time = start_times.get(&summary.season_id.to_lowercase()).cloned();
+    }
+
+    summaries.sort_by(|a, b| {
+        (a.start_time.is_none(), &a.start_time, &a.season_id).cmp(&(b.start_time.is_none(), &b.start_time, &b.season_id))
+    });
+}
+
+/// Fetches one page of competitive updates, newest first. `end` is exclusive.
+pub async fn get_competitive_updates(client: &Client, auth_data: &AuthData, player_id: &str, start: usize, end: usize) -> Result<Vec<CompetitiveUpdate>> {
+    let url = format!(
+        "https://pd.{}.a.pvp.net/mmr/v1/players/{}/competitiveupdates?startIndex={}&endIndex={}&queue={}",
+        "na", player_id, start, end, QueueId::Competitive.as_str()
+    );
+
+    let mut req = Request::new(client, url, Method::GET);
+    req.append_headers(auth_data.get_auth_headers());
+    req.append_headers(common::get_client_headers().await?);
+
+    let res = req.send().await?;
+
+    let data: response::CompetitiveUpdates = res.json().await?;
+
+    Ok(data.matches.into_iter().map(|m| m.into()).collect())
+}
+
+/// Pages through competitive updates until Riot runs out or `limit` updates have been fetched.
+pub async fn get_all_competitive_updates(client: &Client, auth_data: &AuthData, player_id: &str, limit: Option<usize>) -> Result<Vec<CompetitiveUpdate>> {
+    let mut pager = Pager::new(0, limit, COMPETITIVE_UPDATES_PAGE_SIZE);
+    let mut updates = Vec::new();
+
+    while let Some((start, end)) = pager.next_page() {
+        let page = get_competitive_updates(client, auth_data, player_id, start, end).await?;
+
+        pager.advance(page.len(), None);
+        updates.extend(page);
+    }
+
+    Ok(updates)
+}
+
+/// Pages through competitive updates, newest first, until the update for `match_id` has been fetched
+/// or Riot runs out.
+pub async fn get_competitive_updates_through(client: &Client, auth_data: &AuthData, player_id: &str, match_id: &str) -> Result<Vec<CompetitiveUpdate>> {
+    let mut pager = Pager::new(0, None, COMPETITIVE_UPDATES_PAGE_SIZE);
+    let mut updates = Vec::new();
+
+    while let Some((start, end)) = pager.next_page() {
+        let page = get_competitive_updates(client, auth_data, player_id, start, end).await?;
+        let found = page.iter().any(|update| update.match_id == match_id);
+
+        pager.advance(page.len(), None);
+        updates.extend(page);
+
+        if found {
+            pager.stop();
+        }
+    }
+
+    Ok(updates)
+}
+
+mod response {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Mmr {
+        #[serde(rename = "QueueSkills")]
+        pub queue_skills: HashMap<String, QueueSkill>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct QueueSkill {
+        #[serde(rename = "SeasonalInfoBySeasonID")]
+        pub seasonal_info_by_season_id: Option<HashMap<String, SeasonalInfo>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct SeasonalInfo {
+        #[serde(rename = "SeasonID")]
+        pub season_id: String,
+
+        #[serde(rename = "NumberOfWins")]
+        pub number_of_wins: u32,
+
+        #[serde(rename = "NumberOfGames")]
+        pub number_of_games: u32,
+
+        #[serde(rename = "CompetitiveTier")]
+        pub competitive_tier: u32,
+
+        #[serde(rename = "RankedRating")]
+        pub ranked_rating: u32,
+
+        #[serde(rename = "LeaderboardRank")]
+        pub leaderboard_rank: u32,
+
+        #[serde(rename = "WinsByTier")]
+        pub wins_by_tier: Option<HashMap<String, u32>>,
+    }
+
+    /// An act or episode as valorant-api.com lists it.
+    #[derive(Debug, Deserialize)]
+    pub struct SeasonContent {
+        pub uuid: String,
+
+        #[serde(rename = "startTime")]
+        pub start_time: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct CompetitiveUpdates {
+        #[serde(rename = "Matches")]
+        pub matches: Vec<CompetitiveUpdate>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct CompetitiveUpdate {
+        #[serde(rename = "MatchID")]
+        pub match_id: String,
+
+        #[serde(rename = "MapID")]
+        pub map_id: String,
+
+        #[serde(rename = "SeasonID")]
+        pub season_id: String,
+
+        #[serde(rename = "MatchStartTime")]
+        pub match_start_time: u64,
+
+        #[serde(rename = "TierAfterUpdate")]
+        pub tier_after_update: u32,
+
+        #[serde(rename = "TierBeforeUpdate")]
+        pub tier_before_update: u32,
+
+        #[serde(rename = "RankedRatingAfterUpdate")]
+        pub ranked_rating_after_update: u32,
+
+        #[serde(rename = "RankedRatingBeforeUpdate")]
+        pub ranked_rating_before_update: u32,
+
+        #[serde(rename = "RankedRatingEarned")]
+        pub ranked_rating_earned: i32,
+
+        #[serde(rename = "RankedRatingPerformanceBonus", default)]
+        pub ranked_rating_performance_bonus: i32,
+
+        #[serde(rename = "AFKPenalty", default)]
+        pub afk_penalty: i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPETITIVE_UPDATES: &str = include_str!("../tests/fixtures/competitive_updates.json");
+
+    #[test]
+    fn test_deserialize_competitive_updates() {
+        let data: response::CompetitiveUpdates = serde_json::from_str(COMPETITIVE_UPDATES).unwrap();
+        let updates: Vec<CompetitiveUpdate> = data.matches.into_iter().map(|m| m.into()).collect();
+
+        assert_eq!(updates.len(), 2);
+
+        let promotion = &updates[0];
+        assert_eq!(promotion.match_id, "11111111-2222-3333-4444-555555555555");
+        assert_eq!(promotion.start_time, 1672531200000);
+        assert_eq!((promotion.tier_before, promotion.tier_after), (14, 15));
+        assert_eq!((promotion.rr_before, promotion.rr_after), (85, 8));
+        assert_eq!(promotion.rr_earned, 23);
+        assert_eq!(promotion.performance_bonus, 3);
+
+        let loss = &updates[1];
+        assert_eq!(loss.rr_earned, -17);
+        assert_eq!((loss.performance_bonus, loss.afk_penalty), (0, 0));
+    }
+
+    #[test]
+    fn test_season_summary_peak_tier() {
+        let info: response::SeasonalInfo = serde_json::from_str(r#"{
+            "SeasonID": "act",
+            "NumberOfWins": 12,
+            "NumberOfGames": 20,
+            "CompetitiveTier": 14,
+            "RankedRating": 40,
+            "LeaderboardRank": 0,
+            "WinsByTier": {"12": 4, "15": 3, "16": 0, "14": 5}
+        }"#).unwrap();
+
+        let summary: SeasonSummary = info.into();
+
+        assert_eq!(summary.peak_tier, Some(15));
+        assert_eq!(summary.leaderboard_rank, None);
+    }
+
+    #[test]
+    fn test_season_summary_without_wins() {
+        let info: response::SeasonalInfo = serde_json::from_str(r#"{
+            "SeasonID": "act",
+            "NumberOfWins": 0,
+            "NumberOfGames": 1,
+            "CompetitiveTier": 0,
+            "RankedRating": 0,
+            "LeaderboardRank": 120,
+            "WinsByTier": null
+        }"#).unwrap();
+
+        let summary: SeasonSummary = info.into();
+
+        assert_eq!(summary.peak_tier, None);
+        assert_eq!(summary.leaderboard_rank, Some(120));
+    }
+
+    #[test]
+    fn test_seasons_sorted_by_start_time() {
+        let summary = |id: &str| -> SeasonSummary {
+            let info: response::SeasonalInfo = serde_json::from_str(&format!(r#"{{
+                "SeasonID": "{}",
+                "NumberOfWins": 0,
+                "NumberOfGames": 1,
+                "CompetitiveTier": 0,
+                "RankedRating": 0,
+                "LeaderboardRank": 0,
+                "WinsByTier": null
+            }}"#, id)).unwrap();
+
+            info.into()
+        };
+
+        let mut summaries: Vec<SeasonSummary> = ["F0", "a1", "c2", "b3"].map(summary).into();
+        let start_times = HashMap::from([
+            ("f0".to_string(), "2023-01-10T00:00:00Z".to_string()),
+            ("a1".to_string(), "2023-06-27T00:00:00Z".to_string()),
+            ("c2".to_string(), "2022-08-23T00:00:00Z".to_string()),
+        ]);
+
+        sort_by_start_time(&mut summaries, &start_times);
+
+        let order: Vec<&str> = summaries.iter().map(|s| s.season_id.as_str()).collect();
+        assert_eq!(order, ["c2", "F0", "a1", "b3"]);
+        assert_eq!(summaries[1].start_time.as_deref(), Some("2023-01-10T00:00:00Z"));
+        assert_eq!(summaries[3].start_time, None);
+    }
+}
@@ -1,25 +1,403 @@
-use crate::requests::*;
-use crate::auth::AuthData;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
+use futures::StreamExt;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::auth::AuthData;
+use crate::common;
+use crate::history;
+use crate::r#match::{self, Match, PlayerId};
+use crate::requests::*;
+
+#[derive(Error, Debug)]
+pub enum PlayerError {
+    #[error("Invalid Riot ID, expected GameName#Tag")]
+    InvalidRiotId,
+    #[error("No player found for {0}")]
+    NotFound(String),
+}
+
+/// A `GameName#Tag` pair. Riot compares these case-insensitively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiotId {
+    pub game_name: String,
+    pub tagline: String,
+}
+
+impl RiotId {
+    pub fn matches(&self, game_name: &str, tagline: &str) -> bool {
+        self.game_name.to_lowercase() == game_name.to_lowercase()
+            && self.tagline.to_lowercase() == tagline.to_lowercase()
+    }
+}
+
+impl FromStr for RiotId {
+    type Err = PlayerError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (game_name, tagline) = s.trim().rsplit_once('#').ok_or(PlayerError::InvalidRiotId)?;
+
+        if game_name.is_empty() || tagline.is_empty() {
+            return Err(PlayerError::InvalidRiotId);
+        }
 
-pub struct Player {}
+        Ok(Self {
+            game_name: game_name.to_string(),
+            tagline: tagline.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for RiotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.game_name, self.tagline)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Player {
+    pub id: PlayerId,
+    pub ign: String,
+    pub tagline: String,
+}
 
 impl Player {
+    /// Riot ID in the `GameName#Tag` form shown in game.
+    pub fn riot_id(&self) -> String {
+        format!("{}#{}", self.ign, self.tagline)
+    }
+
     pub async fn from_player_id(client: &Client, auth_data: &AuthData, player_id: &str) -> Result<Player> {
-        let url = format!(
-            "https://pd.{}.a.pvp.net/name-service/v2/players?subject={}",
-            "na", player_id
-        );
+        Self::from_player_ids(client, auth_data, &[player_id.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("No player found for {}", player_id))
+    }
 
-        let mut req = Request::new(client, url, Method::GET);
+    /// Resolves many players with a single name-service call.
+    pub async fn from_player_ids(client: &Client, auth_data: &AuthData, player_ids: &[PlayerId]) -> Result<Vec<Player>> {
+        if player_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let url = format!("https://pd.{}.a.pvp.net/name-service/v2/players", "na");
+
+        let mut req = Request::new(client, url, Method::PUT);
         req.append_headers(auth_data.get_auth_headers());
+        req.append_headers(common::get_client_headers().await?);
+        req.set_json_body(player_ids)?;
 
         let res = req.send().await?;
 
-        let data = res.json().await?;
+        let data: Vec<response::Player> = res.json().await?;
+
+        Ok(data.into_iter().map(|p| p.into()).collect())
+    }
+}
+
+impl From<&r#match::Player> for Player {
+    fn from(player: &r#match::Player) -> Self {
+        Self {
+            id: player.id.clone(),
+            ign: player.ign.clone(),
+            tagline: player.tagline.clone(),
+        }
+    }
+}
+
+impl From<response::Player> for Player {
+    fn from(player: response::Player) -> Self {
+        Self {
+            id: player.subject,
+            ign: player.game_name,
+            tagline: player.tag_line,
+        }
+    }
+}
+
+/// Players found by a lookup, in the order asked for, and the IDs name-service didn't know.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerLookup {
+    pub players: Vec<Player>,
+    pub missing: Vec<PlayerId>,
+}
+
+/// Most players a cache remembers before forgetting the ones fetched longest ago.
+pub const PLAYER_CACHE_CAPACITY: usize = 10_000;
+
+/// Short-lived cache of name-service lookups, shared between requests.
+pub struct PlayerCache {
+    ttl: Duration,
+    capacity: usize,
+    players: Mutex<HashMap<PlayerId, (Instant, Player)>>,
+}
+
+impl PlayerCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_capacity(ttl, PLAYER_CACHE_CAPACITY)
+    }
+
+    pub fn with_capacity(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            players: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, player_id: &str) -> Option<Player> {
+        let players = self.players.lock().unwrap();
+
+        players
+            .get(player_id)
+            .filter(|(fetched, _)| fetched.elapsed() < self.ttl)
+            .map(|(_, player)| player.clone())
+    }
+
+    pub fn insert(&self, player: Player) {
+        let mut players = self.players.lock().unwrap();
+
+        players.insert(player.id.clone(), (Instant::now(), player));
+
+        while players.len() > self.capacity {
+            let oldest = players.iter().min_by_key(|(_, (fetched, _))| *fetched).map(|(id, _)| id.clone());
+
+            match oldest {
+                Some(id) => players.remove(&id),
+                None => break,
+            };
+        }
+    }
+
+    /// Returns every known player, including expired entries, for offline lookups.
+    pub fn all(&self) -> Vec<Player> {
+        let players = self.players.lock().unwrap();
+
+        players.values().map(|(_, player)| player.clone()).collect()
+    }
+
+    /// Remembers the Riot ID of everyone in a match, so they can be looked up by name later.
+    pub fn insert_from_match(&self, match_: &Match) {
+        for player in match_.players.iter().filter(|player| !player.ign.is_empty()) {
+            self.insert(player.into());
+        }
+    }
+
+    /// Fills in the Riot ID of players Riot left unnamed in match details, with one name-service
+    /// call for all matches, and remembers everyone else's.
+    pub async fn fill_names(&self, client: &Client, auth_data: &AuthData, matches: &mut [&mut Match]) -> Result<()> {
+        for match_ in matches.iter() {
+            self.insert_from_match(match_);
+        }
+
+        let unnamed: Vec<PlayerId> = matches
+            .iter()
+            .flat_map(|match_| match_.players.iter())
+            .filter(|player| player.ign.is_empty())
+            .map(|player| player.id.clone())
+            .collect();
+
+        self.resolve(client, auth_data, &unnamed).await?;
+
+        for player in matches.iter_mut().flat_map(|match_| match_.players.iter_mut()) {
+            if let Some(cached) = self.get(&player.id).filter(|_| player.ign.is_empty()) {
+                player.ign = cached.ign;
+                player.tagline = cached.tagline;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn find_by_riot_id(&self, riot_id: &RiotId) -> Option<Player> {
+        self.all()
+            .into_iter()
+            .find(|player| riot_id.matches(&player.ign, &player.tagline))
+    }
+
+    /// Resolves players in the given order, only asking Riot for the ones not already cached.
+    pub async fn resolve(&self, client: &Client, auth_data: &AuthData, player_ids: &[PlayerId]) -> Result<PlayerLookup> {
+        let mut missing: Vec<PlayerId> = player_ids
+            .iter()
+            .filter(|id| self.get(id).is_none())
+            .cloned()
+            .collect();
+        missing.sort();
+        missing.dedup();
+
+        for player in Player::from_player_ids(client, auth_data, &missing).await? {
+            self.insert(player);
+        }
+
+        let mut lookup = PlayerLookup {
+            players: Vec::new(),
+            missing: Vec::new(),
+        };
+
+        for id in player_ids {
+            match self.get(id) {
+                Some(player) => lookup.players.push(player),
+                None => lookup.missing.push(id.clone()),
+            }
+        }
+
+        Ok(lookup)
+    }
+}
+
+/// Finds the PUUID behind a Riot ID.
+///
+/// Riot has no public name to PUUID endpoint, so this checks the logged in account, then names
+/// cached from earlier lookups, and finally the players in the logged in account's recent matches.
+/// Match details are loaded one at a time and the scan stops at the first match with the player.
+pub async fn resolve_riot_id(client: &Client, auth_data: &AuthData, cache: &PlayerCache, riot_id: &RiotId) -> Result<PlayerId> {
+    if riot_id.matches(&auth_data.ign, &auth_data.tagline) {
+        return Ok(auth_data.user_id.clone());
+    }
+
+    if let Some(player) = cache.find_by_riot_id(riot_id) {
+        return Ok(player.id);
+    }
+
+    let query = history::HistoryQuery {
+        end: Some(history::HISTORY_PAGE_SIZE),
+        ..Default::default()
+    };
+    let mut history = std::pin::pin!(history::stream_match_history(client, auth_data, &auth_data.user_id, query, true, None));
+
+    while let Some(entry) = history.next().await {
+        if let Some(match_) = entry?.game_data.as_ref() {
+            cache.insert_from_match(match_);
+        }
+
+        if let Some(player) = cache.find_by_riot_id(riot_id) {
+            return Ok(player.id);
+        }
+    }
+
+    Err(PlayerError::NotFound(riot_id.to_string()).into())
+}
+
+/// Accepts either a PUUID or a `GameName#Tag` Riot ID and returns the PUUID.
+pub async fn resolve_player_id(client: &Client, auth_data: &AuthData, cache: &PlayerCache, player: &str) -> Result<PlayerId> {
+    match player.parse::<RiotId>() {
+        Ok(riot_id) => resolve_riot_id(client, auth_data, cache, &riot_id).await,
+        Err(_) => Ok(player.to_string()),
+    }
+}
+
+impl Default for PlayerCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10 * 60))
+    }
+}
+
+mod response {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Player {
+        #[serde(rename = "Subject")]
+        pub subject: String,
+
+        #[serde(rename = "GameName")]
+        pub game_name: String,
+
+        #[serde(rename = "TagLine")]
+        pub tag_line: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(id: &str) -> Player {
+        Player {
+            id: id.to_string(),
+            ign: "Name".to_string(),
+            tagline: "TAG".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cache_hit() {
+        let cache = PlayerCache::default();
+        cache.insert(player("a"));
+
+        assert_eq!(cache.get("a").unwrap().riot_id(), "Name#TAG");
+        assert!(cache.get("b").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fill_names() {
+        let client = Client::new().unwrap();
+        let auth_data: AuthData = serde_json::from_value(serde_json::json!({
+            "access_token": "", "expires_in": 0, "id_token": "", "entitlements_token": "",
+            "user_id": "", "ign": "", "tagline": ""
+        }))
+        .unwrap();
+
+        let mut match_: Match = serde_json::from_str(include_str!("../tests/fixtures/match.json")).unwrap();
+        match_.players[3].ign.clear();
+        match_.players[3].tagline.clear();
+
+        let cache = PlayerCache::default();
+        cache.insert(player("00000000-0000-0000-0000-000000000004"));
+
+        cache.fill_names(&client, &auth_data, &mut [&mut match_]).await.unwrap();
+
+        assert_eq!(match_.players[3].ign, "Name");
+        assert_eq!(cache.get("00000000-0000-0000-0000-000000000001").unwrap().riot_id(), "Alpha#NA1");
+    }
+
+    #[test]
+    fn test_parse_riot_id() {
+        let riot_id: RiotId = "Some#Name#NA1".parse().unwrap();
+
+        assert_eq!(riot_id.game_name, "Some#Name");
+        assert_eq!(riot_id.tagline, "NA1");
+        assert!(riot_id.matches("some#name", "na1"));
+
+        assert!("NoTag".parse::<RiotId>().is_err());
+        assert!("Name#".parse::<RiotId>().is_err());
+    }
+
+    #[test]
+    fn test_find_by_riot_id() {
+        let cache = PlayerCache::default();
+        cache.insert(player("a"));
+
+        let found = cache.find_by_riot_id(&"name#tag".parse().unwrap()).unwrap();
+
+        assert_eq!(found.id, "a");
+    }
+
+    #[test]
+    fn test_cache_expiry() {
+        let cache = PlayerCache::new(Duration::from_secs(0));
+        cache.insert(player("a"));
+
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.all().len(), 1);
+    }
 
-        println!("{:?}", data);
+    #[test]
+    fn test_cache_capacity() {
+        let cache = PlayerCache::with_capacity(Duration::from_secs(60), 2);
+        for id in ["a", "b", "c"] {
+            cache.insert(player(id));
+            std::thread::sleep(Duration::from_millis(1));
+        }
 
-        Ok(Player {})
+        assert_eq!(cache.all().len(), 2);
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("c").is_some());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::content::{self, ContentType};
+use crate::r#match::{Location, MapId};
+use crate::requests::*;
+
+#[derive(Error, Debug)]
+pub enum ProjectionError {
+    #[error("No map data for {0}")]
+    UnknownMap(String),
+}
+
+/// Converts world coordinates from match details to minimap coordinates.
+#[derive(Debug, Clone, Serialize)]
+pub struct MapProjection {
+    pub map_id: MapId,
+    pub name: String,
+    pub minimap_url: Option<String>,
+    pub x_multiplier: f64,
+    pub y_multiplier: f64,
+    pub x_scalar: f64,
+    pub y_scalar: f64,
+}
+
+impl MapProjection {
+    /// Position on the minimap from 0 to 1 on both axes. Riot's world axes are swapped on the minimap.
+    pub fn project(&self, location: Location) -> (f64, f64) {
+        (
+            location.y * self.x_multiplier + self.x_scalar,
+            location.x * self.y_multiplier + self.y_scalar,
+        )
+    }
+
+    /// Pixel position on a square minimap of `size` pixels.
+    pub fn to_pixels(&self, location: Location, size: u32) -> (f64, f64) {
+        let (x, y) = self.project(location);
+
+        (x * size as f64, y * size as f64)
+    }
+
+    /// Looks up the projection for `MatchInfo.map_id`, which is the map's asset path.
+    pub async fn fetch(client: &Client, map_id: &str) -> Result<MapProjection> {
+        let maps = content::get_content::<response::Map>(client, ContentType::Maps).await?;
+
+        maps.into_iter()
+            .find(|map| map.map_url.eq_ignore_ascii_case(map_id))
+            .map(|map| map.into())
+            .ok_or_else(|| ProjectionError::UnknownMap(map_id.to_string()).into())
+    }
+}
+
+impl From<response::Map> for MapProjection {
+    fn from(map: response::Map) -> Self {
+        Self {
+            map_id: map.map_url,
+            name: map.display_name,
+            minimap_url: map.display_icon,
+            x_multiplier: map.x_multiplier,
+            y_multiplier: map.y_multiplier,
+            x_scalar: map.x_scalar_to_add,
+            y_scalar: map.y_scalar_to_add,
+        }
+    }
+}
+
+mod response {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Map {
+        #[serde(rename = "displayName")]
+        pub display_name: String,
+
+        #[serde(rename = "displayIcon")]
+        pub display_icon: Option<String>,
+
+        #[serde(rename = "mapUrl")]
+        pub map_url: String,
+
+        #[serde(rename = "xMultiplier")]
+        pub x_multiplier: f64,
+
+        #[serde(rename = "yMultiplier")]
+        pub y_multiplier: f64,
+
+        #[serde(rename = "xScalarToAdd")]
+        pub x_scalar_to_add: f64,
+
+        #[serde(rename = "yScalarToAdd")]
+        pub y_scalar_to_add: f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ascent() -> MapProjection {
+        MapProjection {
+            map_id: "/Game/Maps/Ascent/Ascent".to_string(),
+            name: "Ascent".to_string(),
+            minimap_url: None,
+            x_multiplier: 0.00007,
+            y_multiplier: -0.00007,
+            x_scalar: 0.813895,
+            y_scalar: 0.573242,
+        }
+    }
+
+    #[test]
+    fn test_project() {
+        let (x, y) = ascent().project(Location { x: 1000.0, y: -5000.0 });
+
+        assert!((x - 0.463895).abs() < 1e-9);
+        assert!((y - 0.503242).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_to_pixels() {
+        let (x, y) = ascent().to_pixels(Location { x: 0.0, y: 0.0 }, 1024);
+
+        assert!((x - 833.42848).abs() < 1e-6);
+        assert!((y - 586.999808).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Matchmaking queue, as Riot names it in match history (`QueueID`) and match details (`queueID`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum QueueId {
+    Competitive,
+    Unrated,
+    Swiftplay,
+    SpikeRush,
+    Deathmatch,
+    Escalation,
+    Replication,
+    Premier,
+    /// Match details leave the queue empty for custom games
+    Custom,
+    NewMap,
+    Snowball,
+    Unknown(String),
+}
+
+impl QueueId {
+    pub fn as_str(&self) -> &str {
+        match self {
+            QueueId::Competitive => "competitive",
+            QueueId::Unrated => "unrated",
+            QueueId::Swiftplay => "swiftplay",
+            QueueId::SpikeRush => "spikerush",
+            QueueId::Deathmatch => "deathmatch",
+            QueueId::Escalation => "ggteam",
+            QueueId::Replication => "onefa",
+            QueueId::Premier => "premier",
+            QueueId::Custom => "custom",
+            QueueId::NewMap => "newmap",
+            QueueId::Snowball => "snowball",
+            QueueId::Unknown(queue) => queue,
+        }
+    }
+
+    /// Name shown in the client's play menu.
+    pub fn display_name(&self) -> &str {
+        match self {
+            QueueId::Competitive => "Competitive",
+            QueueId::Unrated => "Unrated",
+            QueueId::Swiftplay => "Swiftplay",
+            QueueId::SpikeRush => "Spike Rush",
+            QueueId::Deathmatch => "Deathmatch",
+            QueueId::Escalation => "Escalation",
+            QueueId::Replication => "Replication",
+            QueueId::Premier => "Premier",
+            QueueId::Custom => "Custom Game",
+            QueueId::NewMap => "New Map",
+            QueueId::Snowball => "Snowball Fight",
+            QueueId::Unknown(queue) => queue,
+        }
+    }
+}
+
+impl From<String> for QueueId {
+    fn from(queue: String) -> Self {
+        match queue.as_str() {
+            "competitive" => QueueId::Competitive,
+            "unrated" => QueueId::Unrated,
+            "swiftplay" => QueueId::Swiftplay,
+            "spikerush" => QueueId::SpikeRush,
+            "deathmatch" => QueueId::Deathmatch,
+            "ggteam" => QueueId::Escalation,
+            "onefa" => QueueId::Replication,
+            "premier" => QueueId::Premier,
+            "custom" | "" => QueueId::Custom,
+            "newmap" => QueueId::NewMap,
+            "snowball" => QueueId::Snowball,
+            _ => QueueId::Unknown(queue),
+        }
+    }
+}
+
+impl From<&str> for QueueId {
+    fn from(queue: &str) -> Self {
+        queue.to_string().into()
+    }
+}
+
+impl From<QueueId> for String {
+    fn from(queue: QueueId) -> Self {
+        queue.as_str().to_string()
+    }
+}
+
+impl fmt::Display for QueueId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.display_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_id() {
+        assert_eq!(QueueId::from("ggteam"), QueueId::Escalation);
+        assert_eq!(QueueId::from(""), QueueId::Custom);
+        assert_eq!(QueueId::from("hurm"), QueueId::Unknown("hurm".to_string()));
+
+        assert_eq!(QueueId::Replication.as_str(), "onefa");
+        assert_eq!(QueueId::SpikeRush.to_string(), "Spike Rush");
+        assert_eq!(serde_json::to_string(&QueueId::Premier).unwrap(), "\"premier\"");
+    }
+}
@@ -1,10 +1,16 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use reqwest::header;
+use serde::Serialize;
+use serde_json::Value;
 
 use anyhow::Result;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
 use crate::tls;
 
+#[allow(clippy::upper_case_acronyms)]
 pub enum Method {
     GET,
     POST,
@@ -16,16 +22,18 @@ pub struct Request<'a> {
     url: String,
     method: Method,
     body: HashMap<String, String>,
+    json_body: Option<Value>,
     headers: HashMap<String, String>,
 }
 
 impl Request<'_> {
-    pub fn new(client: &Client, url: String, method: Method) -> Request {
+    pub fn new(client: &Client, url: String, method: Method) -> Request<'_> {
         Request {
             client,
             url,
             method,
             body: HashMap::new(),
+            json_body: None,
             headers: HashMap::new(),
         }
     }
@@ -51,6 +59,12 @@ impl Request<'_> {
         }
     }
 
+    /// Replaces the key/value body with an arbitrary JSON value, e.g. an array.
+    pub fn set_json_body<T: Serialize + ?Sized>(&mut self, body: &T) -> Result<()> {
+        self.json_body = Some(serde_json::to_value(body)?);
+        Ok(())
+    }
+
     pub async fn send(&self) -> Result<reqwest::Response> {
         let mut req = match self.method {
             Method::GET => self.client.get(&self.url),
@@ -62,10 +76,10 @@ impl Request<'_> {
             req = req.header(key, value);
         }
 
-        let res = match self.method {
-            Method::GET => req.send().await?,
-            Method::POST => req.json(&self.body).send().await?,
-            Method::PUT => req.json(&self.body).send().await?,
+        let res = match (&self.method, &self.json_body) {
+            (Method::GET, _) => req.send().await?,
+            (_, Some(json_body)) => req.json(json_body).send().await?,
+            (_, None) => req.json(&self.body).send().await?,
         };
 
         Ok(res)
@@ -120,6 +134,83 @@ impl Client {
     }
 }
 
+/// Spaces requests out evenly so long running jobs stay below Riot's limits.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self {
+            interval: per / requests.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits until the next request may be sent. Callers queue up in order.
+    pub async fn wait(&self) {
+        let mut next = self.next.lock().await;
+
+        tokio::time::sleep_until(*next).await;
+
+        *next = Instant::now().max(*next) + self.interval;
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(1))
+    }
+}
+
+/// Walks `start..end` in pages for endpoints taking a `startIndex` and an exclusive `endIndex`.
+#[derive(Debug, Clone)]
+pub struct Pager {
+    next: usize,
+    end: usize,
+    page_size: usize,
+    exhausted: bool,
+}
+
+impl Pager {
+    /// Without an `end`, pages until the endpoint runs out.
+    pub fn new(start: usize, end: Option<usize>, page_size: usize) -> Self {
+        Self {
+            next: start,
+            end: end.unwrap_or(usize::MAX),
+            page_size,
+            exhausted: false,
+        }
+    }
+
+    /// Start and end index of the next page to request, None once paging is done.
+    pub fn next_page(&self) -> Option<(usize, usize)> {
+        if self.exhausted || self.next >= self.end {
+            return None;
+        }
+
+        Some((self.next, self.next + self.page_size.min(self.end - self.next)))
+    }
+
+    /// Moves past the page from `next_page`, which held `len` items out of `total` if the endpoint
+    /// says how many there are. A short page or reaching the total ends paging.
+    pub fn advance(&mut self, len: usize, total: Option<usize>) {
+        let Some((start, end)) = self.next_page() else {
+            return;
+        };
+
+        self.exhausted = len < end - start || total.is_some_and(|total| end >= total);
+        self.next = end;
+    }
+
+    /// Ends paging early, e.g. after a failed request.
+    pub fn stop(&mut self) {
+        self.exhausted = true;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -142,6 +233,55 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter() {
+        let limiter = RateLimiter::new(4, Duration::from_secs(1));
+        let start = Instant::now();
+
+        for _ in 0..5 {
+            limiter.wait().await;
+        }
+
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_pager_pages() {
+        let mut pager = Pager::new(5, Some(48), 20);
+
+        assert_eq!(pager.next_page(), Some((5, 25)));
+        pager.advance(20, None);
+        assert_eq!(pager.next_page(), Some((25, 45)));
+        pager.advance(20, None);
+        assert_eq!(pager.next_page(), Some((45, 48)));
+        pager.advance(3, None);
+        assert_eq!(pager.next_page(), None);
+
+        assert_eq!(Pager::new(0, None, 20).next_page(), Some((0, 20)));
+        assert_eq!(Pager::new(10, Some(10), 20).next_page(), None);
+    }
+
+    #[test]
+    fn test_pager_stops_on_short_page() {
+        let mut pager = Pager::new(0, None, 20);
+
+        pager.advance(20, None);
+        pager.advance(7, None);
+
+        assert_eq!(pager.next_page(), None);
+    }
+
+    #[test]
+    fn test_pager_stops_on_total() {
+        let mut pager = Pager::new(0, None, 20);
+
+        pager.advance(20, Some(45));
+        assert_eq!(pager.next_page(), Some((20, 40)));
+
+        pager.advance(20, Some(40));
+        assert_eq!(pager.next_page(), None);
+    }
+
     #[tokio::test]
     async fn test_append_headers() {
         let client = Client::new().unwrap();
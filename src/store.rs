@@ -0,0 +1,488 @@
+//! Local SQLite copy of match history, so dashboards don't have to hit Riot on every page view.
+//!
+//! Match details are kept as the JSON Riot sent, next to a few columns for filtering, and parsed
+//! again on the way out. `player_stats` has one row per player per match.
+
+use std::path::Path;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use futures::StreamExt;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::auth::AuthData;
+use crate::history::{self, HistoryQuery, MatchHistoryEntry};
+use crate::queue::QueueId;
+use crate::r#match::{Match, PlayerId, TeamColor};
+use crate::requests::{Client, RateLimiter};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS history (
+        player_id TEXT NOT NULL,
+        match_id TEXT NOT NULL,
+        queue_id TEXT NOT NULL,
+        start_time INTEGER NOT NULL,
+        PRIMARY KEY (player_id, match_id)
+    );
+
+    CREATE TABLE IF NOT EXISTS matches (
+        match_id TEXT PRIMARY KEY,
+        queue_id TEXT NOT NULL,
+        map_id TEXT NOT NULL,
+        season_id TEXT NOT NULL,
+        game_start INTEGER NOT NULL,
+        game_length INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS players (
+        player_id TEXT PRIMARY KEY,
+        ign TEXT NOT NULL,
+        tagline TEXT NOT NULL,
+        last_seen INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS player_stats (
+        match_id TEXT NOT NULL,
+        player_id TEXT NOT NULL,
+        team_id TEXT NOT NULL,
+        agent_id TEXT NOT NULL,
+        party_id TEXT NOT NULL,
+        score INTEGER NOT NULL,
+        rounds_played INTEGER NOT NULL,
+        kills INTEGER NOT NULL,
+        deaths INTEGER NOT NULL,
+        assists INTEGER NOT NULL,
+        won INTEGER NOT NULL,
+        PRIMARY KEY (match_id, player_id)
+    );
+
+    CREATE TABLE IF NOT EXISTS syncs (
+        player_id TEXT PRIMARY KEY,
+        synced_until INTEGER NOT NULL
+    );
+";
+
+/// One player's line of the scoreboard of a stored match.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerMatchStats {
+    pub match_id: String,
+    pub player_id: PlayerId,
+    pub queue_id: QueueId,
+    pub map_id: String,
+    pub game_start: u64,
+    pub team_id: TeamColor,
+    pub agent_id: String,
+    pub party_id: String,
+    pub score: u32,
+    pub rounds_played: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub won: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncReport {
+    /// Matches whose details were stored by this sync
+    pub new_matches: usize,
+    /// Matches whose details couldn't be fetched or parsed. They are retried on the next sync.
+    pub failed_matches: Vec<String>,
+    pub last_start_time: Option<u64>,
+}
+
+/// Handle to the database. Clones share the connection.
+#[derive(Debug, Clone)]
+pub struct Store {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Store {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Runs store calls on the blocking thread pool, so SQLite doesn't stall the async runtime.
+    pub async fn run<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Store) -> Result<T> + Send + 'static,
+    {
+        let store = self.clone();
+
+        tokio::task::spawn_blocking(move || f(&store)).await?
+    }
+
+    /// Parses and stores match details along with their players and scoreboard.
+    pub fn save_match(&self, body: &str) -> Result<Match> {
+        let match_ = Match::from_json(body)?;
+        let info = &match_.match_info;
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO matches (match_id, queue_id, map_id, season_id, game_start, game_length, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![info.match_id, info.queue_id.as_str(), info.map_id, info.season_id, info.game_start, info.game_length, body],
+        )?;
+
+        for player in &match_.players {
+            tx.execute(
+                "INSERT INTO players (player_id, ign, tagline, last_seen) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (player_id) DO UPDATE SET ign = COALESCE(NULLIF(?2, ''), ign), tagline = COALESCE(NULLIF(?3, ''), tagline), last_seen = ?4
+                 WHERE last_seen <= ?4",
+                params![player.id, player.ign, player.tagline, info.game_start],
+            )?;
+
+            let won = match_.teams.iter().any(|team| team.team_id == player.team_id && team.won);
+
+            tx.execute(
+                "INSERT OR REPLACE INTO player_stats
+                 (match_id, player_id, team_id, agent_id, party_id, score, rounds_played, kills, deaths, assists, won)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    info.match_id,
+                    player.id,
+                    player.team_id.as_str(),
+                    player.agent_id,
+                    player.party_id,
+                    player.stats.score,
+                    player.stats.rounds_played,
+                    player.stats.kills,
+                    player.stats.deaths,
+                    player.stats.assists,
+                    won,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+
+        Ok(match_)
+    }
+
+    pub fn save_history_entry(&self, player_id: &str, entry: &MatchHistoryEntry) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO history (player_id, match_id, queue_id, start_time) VALUES (?1, ?2, ?3, ?4)",
+            params![player_id, entry.match_id, entry.queue_id.as_str(), entry.start_time],
+        )?;
+
+        Ok(())
+    }
+
+    /// Matches in a player's stored history whose details haven't been stored yet.
+    pub fn missing_details(&self, player_id: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT h.match_id FROM history h LEFT JOIN matches m ON m.match_id = h.match_id
+             WHERE h.player_id = ?1 AND m.match_id IS NULL
+             ORDER BY h.start_time",
+        )?;
+
+        let match_ids = stmt.query_map(params![player_id], |row| row.get(0))?;
+
+        Ok(match_ids.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Start time of the newest match synced for a player.
+    pub fn last_start_time(&self, player_id: &str) -> Result<Option<u64>> {
+        let last = self.conn.lock().unwrap().query_row(
+            "SELECT MAX(start_time) FROM history WHERE player_id = ?1",
+            params![player_id],
+            |row| row.get(0),
+        )?;
+
+        Ok(last)
+    }
+
+    /// Start time of the newest match of the last sync that walked the player's history to the end.
+    /// Unlike `last_start_time`, an interrupted sync leaves it alone.
+    pub fn synced_until(&self, player_id: &str) -> Result<Option<u64>> {
+        let until = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT synced_until FROM syncs WHERE player_id = ?1", params![player_id], |row| row.get(0))
+            .optional()?;
+
+        Ok(until)
+    }
+
+    pub fn set_synced_until(&self, player_id: &str, start_time: u64) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO syncs (player_id, synced_until) VALUES (?1, ?2)",
+            params![player_id, start_time],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn get_match(&self, match_id: &str) -> Result<Option<Match>> {
+        let body: Option<String> = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT data FROM matches WHERE match_id = ?1", params![match_id], |row| row.get(0))
+            .optional()?;
+
+        body.map(|body| Match::from_json(&body)).transpose()
+    }
+
+    /// A player's stored history, newest first, with match details where they were synced. Matches
+    /// whose stored details no longer parse are logged and left out.
+    pub fn history(&self, player_id: &str, queue: Option<&QueueId>, start: usize, end: Option<usize>) -> Result<Vec<MatchHistoryEntry>> {
+        let rows: Vec<(String, String, u64, Option<String>)> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT h.match_id, h.queue_id, h.start_time, m.data FROM history h
+                 LEFT JOIN matches m ON m.match_id = h.match_id
+                 WHERE h.player_id = ?1 AND (?2 IS NULL OR h.queue_id = ?2)
+                 ORDER BY h.start_time DESC LIMIT ?3 OFFSET ?4",
+            )?;
+
+            let limit = end.map_or(-1, |end| end.saturating_sub(start) as i64);
+            let rows = stmt.query_map(params![player_id, queue.map(QueueId::as_str), limit, start as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?;
+
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(match_id, queue_id, start_time, data)| {
+                let game_data = match data.map(|data| Match::from_json(&data)).transpose() {
+                    Ok(game_data) => game_data,
+                    Err(err) => {
+                        log::warn!("skipping stored match {} that no longer parses: {}", match_id, err);
+                        return None;
+                    }
+                };
+                let queue_id: QueueId = queue_id.into();
+
+                Some(MatchHistoryEntry {
+                    match_id,
+                    match_type: (&queue_id).into(),
+                    queue_id,
+                    start_time,
+                    game_data,
+                })
+            })
+            .collect())
+    }
+
+    /// Scoreboard lines of a player across all stored matches, newest first.
+    pub fn player_stats(&self, player_id: &str) -> Result<Vec<PlayerMatchStats>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT s.match_id, s.player_id, m.queue_id, m.map_id, m.game_start, s.team_id, s.agent_id, s.party_id,
+                    s.score, s.rounds_played, s.kills, s.deaths, s.assists, s.won
+             FROM player_stats s JOIN matches m ON m.match_id = s.match_id
+             WHERE s.player_id = ?1
+             ORDER BY m.game_start DESC",
+        )?;
+
+        let stats = stmt.query_map(params![player_id], |row| {
+            Ok(PlayerMatchStats {
+                match_id: row.get(0)?,
+                player_id: row.get(1)?,
+                queue_id: row.get::<_, String>(2)?.into(),
+                map_id: row.get(3)?,
+                game_start: row.get(4)?,
+                team_id: row.get::<_, String>(5)?.into(),
+                agent_id: row.get(6)?,
+                party_id: row.get(7)?,
+                score: row.get(8)?,
+                rounds_played: row.get(9)?,
+                kills: row.get(10)?,
+                deaths: row.get(11)?,
+                assists: row.get(12)?,
+                won: row.get(13)?,
+            })
+        })?;
+
+        Ok(stats.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// Fetches the matches a player played since the last complete sync and stores them. History
+/// entries are saved as they arrive, and only a walk that reached the previous sync moves the
+/// starting point forward, so an interrupted sync keeps what it fetched and the next one fills the
+/// rest. Matches whose details failed before are retried.
+pub async fn sync(client: &Client, auth_data: &AuthData, store: &Store, player_id: &str, limiter: Option<&RateLimiter>) -> Result<SyncReport> {
+    let owned_id = player_id.to_string();
+    let synced_until = store.run(move |store| store.synced_until(&owned_id)).await?;
+
+    let mut entries = pin!(history::stream_match_history(client, auth_data, player_id, HistoryQuery::default(), false, limiter));
+    let mut newest = None;
+
+    while let Some(entry) = entries.next().await {
+        let entry = entry?;
+
+        if synced_until.is_some_and(|until| entry.start_time <= until) {
+            break;
+        }
+
+        newest = newest.max(Some(entry.start_time));
+
+        let owned_id = player_id.to_string();
+        store.run(move |store| store.save_history_entry(&owned_id, &entry)).await?;
+    }
+
+    if let Some(newest) = newest {
+        let owned_id = player_id.to_string();
+        store.run(move |store| store.set_synced_until(&owned_id, newest)).await?;
+    }
+
+    let mut report = SyncReport::default();
+
+    let owned_id = player_id.to_string();
+    for match_id in store.run(move |store| store.missing_details(&owned_id)).await? {
+        if let Some(limiter) = limiter {
+            limiter.wait().await;
+        }
+
+        let saved = match Match::fetch_json(client, auth_data, &match_id).await {
+            Ok(body) => store.run(move |store| store.save_match(&body).map(drop)).await,
+            Err(err) => Err(err),
+        };
+
+        match saved {
+            Ok(()) => report.new_matches += 1,
+            Err(err) => {
+                log::warn!("couldn't sync match {}: {}", match_id, err);
+                report.failed_matches.push(match_id);
+            }
+        }
+    }
+
+    let owned_id = player_id.to_string();
+    report.last_start_time = store.run(move |store| store.last_start_time(&owned_id)).await?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::MatchType;
+
+    const MATCH: &str = include_str!("../tests/fixtures/match.json");
+
+    const PLAYER: &str = "00000000-0000-0000-0000-000000000001";
+
+    fn entry(match_id: &str, start_time: u64) -> MatchHistoryEntry {
+        MatchHistoryEntry {
+            match_id: match_id.to_string(),
+            match_type: MatchType::Competitive,
+            queue_id: QueueId::Competitive,
+            start_time,
+            game_data: None,
+        }
+    }
+
+    #[test]
+    fn test_save_match() {
+        let store = Store::in_memory().unwrap();
+
+        store.save_match(MATCH).unwrap();
+        store.save_match(MATCH).unwrap();
+
+        let match_ = store.get_match("11111111-2222-3333-4444-555555555555").unwrap().unwrap();
+        assert_eq!(match_.players.len(), 4);
+        assert!(store.get_match("missing").unwrap().is_none());
+
+        let stats = store.player_stats(PLAYER).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].kills, stats[0].won), (2, false));
+        assert_eq!(stats[0].queue_id, QueueId::Competitive);
+    }
+
+    #[test]
+    fn test_history() {
+        let store = Store::in_memory().unwrap();
+        assert_eq!(store.last_start_time(PLAYER).unwrap(), None);
+
+        store.save_match(MATCH).unwrap();
+        store.save_history_entry(PLAYER, &entry("11111111-2222-3333-4444-555555555555", 1672531200000)).unwrap();
+        store.save_history_entry(PLAYER, &entry("not-synced", 1672617600000)).unwrap();
+
+        assert_eq!(store.last_start_time(PLAYER).unwrap(), Some(1672617600000));
+        assert_eq!(store.missing_details(PLAYER).unwrap(), vec!["not-synced"]);
+
+        let history = store.history(PLAYER, None, 0, None).unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].game_data.is_none());
+        assert!(history[1].game_data.is_some());
+
+        assert_eq!(store.history(PLAYER, None, 1, Some(2)).unwrap().len(), 1);
+        assert!(store.history(PLAYER, Some(&QueueId::Unrated), 0, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_history_skips_unparsable_matches() {
+        let store = Store::in_memory().unwrap();
+
+        store.save_match(MATCH).unwrap();
+        store.save_history_entry(PLAYER, &entry("11111111-2222-3333-4444-555555555555", 1672531200000)).unwrap();
+        store.save_history_entry(PLAYER, &entry("not-synced", 1672617600000)).unwrap();
+        store.conn.lock().unwrap().execute("UPDATE matches SET data = '{}'", []).unwrap();
+
+        let history = store.history(PLAYER, None, 0, None).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].match_id, "not-synced");
+    }
+
+    #[test]
+    fn test_empty_names_keep_known_names() {
+        let store = Store::in_memory().unwrap();
+
+        store.save_match(MATCH).unwrap();
+        store.save_match(&MATCH.replace(r#""gameName": "Alpha""#, r#""gameName": """#)).unwrap();
+
+        let ign: String = store
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT ign FROM players WHERE player_id = ?1", params![PLAYER], |row| row.get(0))
+            .unwrap();
+        assert_eq!(ign, "Alpha");
+    }
+
+    #[test]
+    fn test_synced_until() {
+        let store = Store::in_memory().unwrap();
+        store.save_history_entry(PLAYER, &entry("interrupted", 1672617600000)).unwrap();
+
+        assert_eq!(store.synced_until(PLAYER).unwrap(), None);
+
+        store.set_synced_until(PLAYER, 1672531200000).unwrap();
+        store.set_synced_until(PLAYER, 1672617600000).unwrap();
+
+        assert_eq!(store.synced_until(PLAYER).unwrap(), Some(1672617600000));
+    }
+
+    #[tokio::test]
+    async fn test_run() {
+        let store = Store::in_memory().unwrap();
+        store.run(|store| store.save_match(MATCH).map(drop)).await.unwrap();
+
+        let stats = store.run(|store| store.player_stats(PLAYER)).await.unwrap();
+        assert_eq!(stats.len(), 1);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use crate::r#match::{DamageSource, Match, PlayerId, RoundResult, RoundResultCode, TeamColor};
+
+/// Time from plant to detonation.
+pub const SPIKE_TIMER: u32 = 45000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum TimelineEvent {
+    Kill {
+        killer: PlayerId,
+        victim: PlayerId,
+        assistants: Vec<PlayerId>,
+        /// None when Riot left out the finishing damage
+        source: Option<DamageSource>,
+    },
+    SpikePlant {
+        planter: PlayerId,
+        site: String,
+    },
+    SpikeDefuse {
+        defuser: PlayerId,
+    },
+    RoundEnd {
+        winning_team: TeamColor,
+        result_code: RoundResultCode,
+    },
+}
+
+impl TimelineEvent {
+    fn players(&self) -> Vec<&PlayerId> {
+        match self {
+            TimelineEvent::Kill { killer, victim, assistants, .. } => {
+                [killer, victim].into_iter().chain(assistants.iter()).collect()
+            }
+            TimelineEvent::SpikePlant { planter, .. } => vec![planter],
+            TimelineEvent::SpikeDefuse { defuser } => vec![defuser],
+            TimelineEvent::RoundEnd { .. } => Vec::new(),
+        }
+    }
+
+    /// Tie breaker for events at the same time, so a round always ends last.
+    fn order(&self) -> u8 {
+        match self {
+            TimelineEvent::Kill { .. } => 0,
+            TimelineEvent::SpikePlant { .. } => 1,
+            TimelineEvent::SpikeDefuse { .. } => 2,
+            TimelineEvent::RoundEnd { .. } => 3,
+        }
+    }
+}
+
+/// `game_time` is only known for rounds with at least one kill, since Riot only timestamps kills
+/// in game time. Round ends are estimated from the spike and round timers.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEntry {
+    pub round: u32,
+    pub round_time: u32,
+    pub game_time: Option<u32>,
+    #[serde(flatten)]
+    pub event: TimelineEvent,
+    /// `GameName#Tag` of the named players in the event
+    pub riot_ids: BTreeMap<PlayerId, String>,
+}
+
+fn round_end_time(round: &RoundResult, last_event: u32) -> u32 {
+    match (&round.result_code, &round.plant, &round.defuse) {
+        (RoundResultCode::Detonate, Some(plant), _) => plant.round_time + SPIKE_TIMER,
+        (RoundResultCode::Defuse, _, Some(defuse)) => defuse.round_time,
+        _ => last_event,
+    }
+}
+
+/// Kills, spike events and round ends in the order they happened.
+/// Kills missing their round or round time can't be placed, so they are left out.
+pub fn timeline(match_: &Match) -> Vec<TimelineEntry> {
+    let round_starts: HashMap<u32, u32> = match_
+        .kills
+        .iter()
+        .filter_map(|k| Some((k.round?, k.game_time?.saturating_sub(k.round_time?))))
+        .collect();
+
+    let game_time = |round: u32, round_time: u32| round_starts.get(&round).map(|start| start + round_time);
+
+    let mut entries: Vec<TimelineEntry> = match_
+        .kills
+        .iter()
+        .filter_map(|kill| {
+            Some(TimelineEntry {
+                round: kill.round?,
+                round_time: kill.round_time?,
+                game_time: kill.game_time,
+                event: TimelineEvent::Kill {
+                    killer: kill.killer.clone(),
+                    victim: kill.victim.clone(),
+                    assistants: kill.assistants.clone(),
+                    source: kill.finishing_damage.as_ref().map(|damage| damage.source.clone()),
+                },
+                riot_ids: BTreeMap::new(),
+            })
+        })
+        .collect();
+
+    for round in match_.round_results.iter() {
+        let mut last_event = match_
+            .kills
+            .iter()
+            .filter(|k| k.round == Some(round.round_num))
+            .filter_map(|k| k.round_time)
+            .max()
+            .unwrap_or(0);
+
+        if let Some(plant) = round.plant.as_ref() {
+            last_event = last_event.max(plant.round_time);
+
+            entries.push(TimelineEntry {
+                round: round.round_num,
+                round_time: plant.round_time,
+                game_time: game_time(round.round_num, plant.round_time),
+                event: TimelineEvent::SpikePlant {
+                    planter: plant.planter.clone(),
+                    site: plant.site.clone(),
+                },
+                riot_ids: BTreeMap::new(),
+            });
+        }
+
+        if let Some(defuse) = round.defuse.as_ref() {
+            last_event = last_event.max(defuse.round_time);
+
+            entries.push(TimelineEntry {
+                round: round.round_num,
+                round_time: defuse.round_time,
+                game_time: game_time(round.round_num, defuse.round_time),
+                event: TimelineEvent::SpikeDefuse {
+                    defuser: defuse.defuser.clone(),
+                },
+                riot_ids: BTreeMap::new(),
+            });
+        }
+
+        let end = round_end_time(round, last_event);
+
+        entries.push(TimelineEntry {
+            round: round.round_num,
+            round_time: end,
+            game_time: game_time(round.round_num, end),
+            event: TimelineEvent::RoundEnd {
+                winning_team: round.winning_team.clone(),
+                result_code: round.result_code.clone(),
+            },
+            riot_ids: BTreeMap::new(),
+        });
+    }
+
+    for entry in entries.iter_mut() {
+        entry.riot_ids = entry
+            .event
+            .players()
+            .into_iter()
+            .filter_map(|player| Some((player.clone(), match_.riot_id(player)?)))
+            .collect();
+    }
+
+    entries.sort_by_key(|e| (e.round, e.round_time, e.event.order()));
+
+    entries
+}
+
+/// One JSON object per line.
+pub fn to_ndjson(entries: &[TimelineEntry]) -> serde_json::Result<String> {
+    let mut ndjson = String::new();
+
+    for entry in entries {
+        ndjson.push_str(&serde_json::to_string(entry)?);
+        ndjson.push('\n');
+    }
+
+    Ok(ndjson)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH: &str = include_str!("../tests/fixtures/match.json");
+
+    #[test]
+    fn test_timeline() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let entries = timeline(&match_);
+
+        assert_eq!(entries.len(), 14);
+
+        let first_round: Vec<u8> = entries.iter().filter(|e| e.round == 0).map(|e| e.event.order()).collect();
+        assert_eq!(first_round, vec![0, 0, 1, 0, 3]);
+
+        let plant = &entries[2];
+        assert_eq!((plant.round_time, plant.game_time), (40000, Some(65000)));
+
+        let detonation = &entries[4];
+        assert_eq!(detonation.round_time, 85000);
+
+        let defuse_round_end = entries.last().unwrap();
+        assert_eq!(defuse_round_end.round_time, 68000);
+        assert!(matches!(defuse_round_end.event, TimelineEvent::RoundEnd { result_code: RoundResultCode::Defuse, .. }));
+    }
+
+    #[test]
+    fn test_unknown_result_code() {
+        let json = MATCH.replace(r#""roundResultCode": "Elimination""#, r#""roundResultCode": """#);
+        let match_: Match = serde_json::from_str(&json).unwrap();
+        let round = match_.round_results.iter().find(|r| r.result_code == RoundResultCode::Unknown).unwrap();
+
+        let entries = timeline(&match_);
+        let round_entries: Vec<&TimelineEntry> = entries.iter().filter(|e| e.round == round.round_num).collect();
+        let (end, events) = round_entries.split_last().unwrap();
+
+        assert!(matches!(end.event, TimelineEvent::RoundEnd { result_code: RoundResultCode::Unknown, .. }));
+        assert_eq!(end.round_time, events.iter().map(|e| e.round_time).max().unwrap());
+    }
+
+    #[test]
+    fn test_to_ndjson() {
+        let match_: Match = serde_json::from_str(MATCH).unwrap();
+        let ndjson = to_ndjson(&timeline(&match_)).unwrap();
+
+        let lines: Vec<&str> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 14);
+
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["type"], "Kill");
+        assert_eq!(first["round"], 0);
+        assert_eq!(first["riot_ids"][first["killer"].as_str().unwrap()], "Alpha#NA1");
+    }
+}
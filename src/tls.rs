@@ -26,7 +26,7 @@ pub fn create_tls_config() -> Result<ClientConfig> {
     Ok(config)
 }
 
-const CIPHER_SUITES: &'static [&str] = &[
+const CIPHER_SUITES: &[&str] = &[
     "TLS13_CHACHA20_POLY1305_SHA256",
     "TLS13_AES_128_GCM_SHA256",
     "TLS13_AES_256_GCM_SHA384",